@@ -0,0 +1,144 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hint::black_box,
+    io::{BufRead, Cursor},
+    time::{Duration, Instant},
+};
+
+use fall_challenge_2023::{state::GameState, trace::DroneTrace};
+
+// Recorded input streams, see `replay --format inputs`
+const STATES: [&str; 4] = ["opening", "midgame", "monster", "two_drones"];
+const ITERATIONS: u32 = 2000;
+const SEARCH_DEPTHS: [f64; 3] = [1.0, 2.0, 3.0];
+const SEARCH_ITERATIONS: u32 = 3;
+// Milliseconds CodinGame gives for a turn
+const TURN_BUDGET: f64 = 50.0;
+
+// State after every turn of the recorded input
+fn load(name: &str) -> GameState {
+    let path = format!("benches/states/{}.txt", name);
+    let input = fs::read_to_string(&path).unwrap_or_else(|_| panic!("cannot read {}", path));
+    let mut input = Cursor::new(input);
+
+    let mut state = GameState::new(&mut input);
+    while !input.fill_buf().unwrap().is_empty() {
+        state.update_state(&mut input);
+    }
+    state
+}
+
+fn report(name: &str, elapsed: Duration, iterations: u32) {
+    let micros = elapsed.as_secs_f64() * 1_000_000.0 / iterations as f64;
+    println!("  {:<24} {:>12.2} µs/iter", name, micros);
+}
+
+fn bench_clone(state: &GameState) {
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(state.clone());
+    }
+    report("clone", started.elapsed(), ITERATIONS);
+}
+
+// Clones are made beforehand so that only the turn itself is timed
+fn bench_apply_moves(state: &GameState, drone_id: i32) {
+    let moves = state.get_possible_moves(drone_id);
+    let mut states = (0..ITERATIONS).map(|_| state.clone()).collect::<Vec<_>>();
+
+    let started = Instant::now();
+    for (i, state) in states.iter_mut().enumerate() {
+        let m = moves[i % moves.len()].clone();
+        state.apply_moves(&HashMap::from([(drone_id, m)]));
+    }
+    report("apply_moves", started.elapsed(), ITERATIONS);
+    black_box(states);
+}
+
+fn bench_evaluate(state: &GameState) {
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(state.evaluate(None));
+    }
+    report("evaluate", started.elapsed(), ITERATIONS);
+
+    let mut state = state.clone();
+    state.params.value_net_weight = 1.0;
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(state.evaluate(None));
+    }
+    report("evaluate value net", started.elapsed(), ITERATIONS);
+}
+
+fn bench_search(state: &GameState, drone_id: i32) {
+    for depth in SEARCH_DEPTHS {
+        let mut state = state.clone();
+        state.params.search_depth = depth;
+
+        let mut nodes = 0;
+        let started = Instant::now();
+        for _ in 0..SEARCH_ITERATIONS {
+            let mut trace = DroneTrace::default();
+            black_box(state.find_best_move(drone_id, None, &mut trace));
+            nodes += trace.nodes;
+        }
+        let elapsed = started.elapsed();
+
+        report(
+            &format!("search depth {}", depth),
+            elapsed,
+            SEARCH_ITERATIONS,
+        );
+        println!(
+            "  {:<24} {:>12.0} nodes/s",
+            "",
+            nodes as f64 / elapsed.as_secs_f64()
+        );
+    }
+}
+
+// Whole turn of every drone as played online: endgame plans, book, race, scares and search
+// with the default time limit, then without it for the worst case at the default depth.
+// The time is only compared with the budget, since it depends on the machine.
+fn bench_turn(name: &str, state: &GameState) {
+    for search_time in [state.params.search_time, 0.0] {
+        let mut state = state.clone();
+        state.params.search_time = search_time;
+
+        let started = Instant::now();
+        for _ in 0..SEARCH_ITERATIONS {
+            black_box(state.next_moves());
+        }
+        let elapsed = started.elapsed();
+        let limit = if search_time > 0.0 { "" } else { " no limit" };
+        report(&format!("{}{}", name, limit), elapsed, SEARCH_ITERATIONS);
+
+        let millis = elapsed.as_secs_f64() * 1000.0 / SEARCH_ITERATIONS as f64;
+        if millis > TURN_BUDGET {
+            println!("  {:<24} over the {} ms budget", "", TURN_BUDGET);
+        }
+    }
+}
+
+// Timings of the search building blocks over fixed recorded states,
+// run with `cargo bench` and compared by hand between changes
+fn main() {
+    for name in STATES {
+        let state = load(name);
+        let mut drone_ids = state.my_drones.keys().copied().collect::<Vec<_>>();
+        drone_ids.sort();
+
+        println!("{} (turn {})", name, state.turn);
+        bench_clone(&state);
+        bench_apply_moves(&state, drone_ids[0]);
+        bench_evaluate(&state);
+        bench_search(&state, drone_ids[0]);
+        bench_turn("turn", &state);
+
+        let mut value_net_state = state.clone();
+        value_net_state.params.value_net_weight = 1.0;
+        bench_turn("turn value net", &value_net_state);
+    }
+}
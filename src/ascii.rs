@@ -0,0 +1,164 @@
+use std::fmt::Write;
+
+use crate::{
+    protocol::RadarDir,
+    state::{CreatureKind, GameState, MAP_SIZE, SURFACE_Y},
+};
+
+// Terminal cells are about twice as tall as they are wide
+const GRID_WIDTH: i32 = 60;
+const GRID_HEIGHT: i32 = 30;
+const RADAR_DIRS: [RadarDir; 4] = [RadarDir::TL, RadarDir::TR, RadarDir::BL, RadarDir::BR];
+
+fn cell(x: i32, y: i32) -> (usize, usize) {
+    let column = (x * GRID_WIDTH / MAP_SIZE).clamp(0, GRID_WIDTH - 1);
+    let row = (y * GRID_HEIGHT / MAP_SIZE).clamp(0, GRID_HEIGHT - 1);
+    (row as usize, column as usize)
+}
+
+fn glyph(kind: CreatureKind) -> char {
+    match kind {
+        CreatureKind::Monster => 'M',
+        CreatureKind::Type0 => '0',
+        CreatureKind::Type1 => '1',
+        CreatureKind::Type2 => '2',
+    }
+}
+
+fn sorted(ids: impl IntoIterator<Item = i32>) -> Vec<i32> {
+    let mut ids = ids.into_iter().collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+fn render_grid(state: &GameState) -> Vec<Vec<char>> {
+    let mut grid = vec![vec![' '; GRID_WIDTH as usize]; GRID_HEIGHT as usize];
+
+    let (surface_row, _) = cell(0, SURFACE_Y as i32);
+    grid[surface_row].fill('~');
+    for kind in CreatureKind::FISH {
+        let (min_y, _) = kind.habitat();
+        let (row, _) = cell(0, min_y);
+        grid[row].fill('-');
+    }
+
+    let creatures = state
+        .creatures
+        .values()
+        .filter(|creature| !state.departed_creatures.contains(&creature.id))
+        .collect::<Vec<_>>();
+
+    // Hidden creatures are somewhere in the region the radar allows
+    for creature in creatures.iter() {
+        let is_on_radar = state
+            .my_drones
+            .keys()
+            .any(|drone_id| state.radar.direction(*drone_id, creature.id).is_some());
+        if creature.x.is_some() || !is_on_radar {
+            continue;
+        }
+
+        let (min_x, min_y, max_x, max_y) = state.estimated_region(creature.id);
+        let (min_row, min_column) = cell(min_x, min_y);
+        let (max_row, max_column) = cell(max_x, max_y);
+        for row in grid.iter_mut().take(max_row + 1).skip(min_row) {
+            for cell in row.iter_mut().take(max_column + 1).skip(min_column) {
+                if *cell == ' ' {
+                    *cell = '.';
+                }
+            }
+        }
+    }
+
+    for creature in creatures {
+        if let (Some(x), Some(y)) = (creature.x, creature.y) {
+            let (row, column) = cell(x, y);
+            grid[row][column] = glyph(creature.kind);
+        }
+    }
+
+    for (drones, glyph) in [(&state.their_drones, '&'), (&state.my_drones, '@')] {
+        for drone in drones.values() {
+            let (row, column) = cell(drone.x, drone.y);
+            grid[row][column] = glyph;
+        }
+    }
+
+    grid
+}
+
+fn render_sidebar(state: &GameState) -> Vec<String> {
+    let mut sidebar = vec![
+        format!("turn {}", state.turn),
+        format!("score {} - {}", state.my_score, state.foe_score),
+        format!("saved {:?}", sorted(state.saved_creatures_for(true))),
+        format!("foe saved {:?}", sorted(state.saved_creatures_for(false))),
+    ];
+
+    for (is_mine, glyph) in [(true, '@'), (false, '&')] {
+        let saved = state.saved_creatures_for(is_mine);
+        let mut drones = state.drones_for(is_mine).values().collect::<Vec<_>>();
+        drones.sort_by_key(|drone| drone.id);
+
+        for drone in drones {
+            sidebar.push(String::new());
+            sidebar.push(format!(
+                "{} drone {} ({}, {}) battery {}{}",
+                glyph,
+                drone.id,
+                drone.x,
+                drone.y,
+                drone.battery,
+                if drone.emergency == 1 {
+                    " emergency"
+                } else {
+                    ""
+                }
+            ));
+            sidebar.push(format!(
+                "  carries {:?}",
+                sorted(state.unsaved_creatures_for_drone(drone.id, &saved))
+            ));
+
+            if is_mine {
+                let mut radar = "  radar".to_string();
+                for direction in RADAR_DIRS {
+                    let creature_ids = state.radar.creatures_in(drone.id, direction);
+                    write!(radar, " {} {:?}", direction.as_str(), creature_ids).unwrap();
+                }
+                sidebar.push(radar);
+            }
+        }
+    }
+
+    sidebar.push(String::new());
+    sidebar.push("@ mine  & foe  0 1 2 fish  M monster  . hidden".to_string());
+    sidebar
+}
+
+// Downscaled map of what the state knows, with scores and scans on the side
+pub fn render(state: &GameState) -> String {
+    let grid = render_grid(state);
+    let sidebar = render_sidebar(state);
+    let border = format!("+{}+", "-".repeat(GRID_WIDTH as usize));
+    let width = border.len();
+
+    let mut lines = vec![border.clone()];
+    lines.extend(
+        grid.into_iter()
+            .map(|row| format!("|{}|", row.into_iter().collect::<String>())),
+    );
+    lines.push(border);
+
+    let mut output = String::new();
+    for i in 0..usize::max(lines.len(), sidebar.len()) {
+        let line = lines.get(i).map_or("", String::as_str);
+        match sidebar.get(i) {
+            Some(side) if !side.is_empty() => {
+                writeln!(output, "{:width$}  {}", line, side).unwrap()
+            }
+            _ => writeln!(output, "{}", line).unwrap(),
+        }
+    }
+    output
+}
@@ -0,0 +1,116 @@
+use std::{env, fs};
+
+use fall_challenge_2023::state::League;
+
+// CodinGame limit on the size of the submitted source
+const MAX_SOURCE_LENGTH: usize = 100_000;
+const CRATE_NAME: &str = "fall_challenge_2023";
+const LOCAL_ONLY: &str = "#[cfg(feature = \"local\")]";
+const SUBMITTED_LEAGUE: &str = "pub const SUBMITTED_LEAGUE: &str = \"\";";
+
+// Lines of the file with the local only items removed, along with comments, blank lines
+// and indentation
+fn strip(source: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut source_lines = source.lines();
+
+    while let Some(line) = source_lines.next() {
+        let trimmed = line.trim();
+
+        if trimmed == LOCAL_ONLY {
+            // Skip the next item, up to its closing brace when it spans several lines
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let item = source_lines.next().unwrap_or_default();
+            if item.trim_end().ends_with('{') {
+                for item_line in source_lines.by_ref() {
+                    let closing = item_line.strip_prefix(indent).unwrap_or_default();
+                    if closing == "}" || closing == "};" {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            continue;
+        }
+        lines.push(trimmed.to_string());
+    }
+
+    lines
+}
+
+// The library with its modules inlined, followed by the binary using it through `crate::`,
+// with the league baked in when given
+fn bundle(league: Option<&str>) -> String {
+    let mut output = Vec::new();
+
+    let lib = fs::read_to_string("src/lib.rs").expect("cannot read src/lib.rs");
+    for line in strip(&lib) {
+        match line
+            .strip_prefix("pub mod ")
+            .and_then(|module| module.strip_suffix(';'))
+        {
+            Some(module) => {
+                let path = format!("src/{}.rs", module);
+                let source =
+                    fs::read_to_string(&path).unwrap_or_else(|_| panic!("cannot read {}", path));
+                output.push(format!("pub mod {} {{", module));
+                output.extend(strip(&source).into_iter().map(|line| match league {
+                    Some(league) if line == SUBMITTED_LEAGUE => {
+                        format!("pub const SUBMITTED_LEAGUE: &str = \"{}\";", league)
+                    }
+                    _ => line,
+                }));
+                output.push("}".to_string());
+            }
+            None => output.push(line),
+        }
+    }
+
+    let main = fs::read_to_string("src/main.rs").expect("cannot read src/main.rs");
+    for line in strip(&main) {
+        output.push(line.replace(&format!("{}::", CRATE_NAME), "crate::"));
+    }
+
+    output.join("\n") + "\n"
+}
+
+// Write the submission file, `cargo run --bin bundle -- [--output FILE] [--league LEAGUE]`
+// from the crate root. Without a league, the bot tells Bronze from Wood 1 by the monsters,
+// so a Wood 2 submission needs `--league wood2`.
+fn main() {
+    let mut output = "submission.rs".to_string();
+    let mut league = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--output" => output = value,
+            "--league" => {
+                League::parse(&value).unwrap_or_else(|error| panic!("{}", error));
+                league = Some(value);
+            }
+            _ => panic!(
+                "unknown option {}, available options: --output --league",
+                arg
+            ),
+        }
+    }
+
+    let source = bundle(league.as_deref());
+    fs::write(&output, &source).expect("cannot write the output file");
+
+    let length = source.chars().count();
+    eprintln!("{} written, {} characters", output, length);
+    if length > MAX_SOURCE_LENGTH {
+        eprintln!(
+            "warning: over the {} characters accepted by CodinGame",
+            MAX_SOURCE_LENGTH
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::{collections::HashMap, io::Cursor, thread};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    eval::Params,
+    opening::{BookEntry, BookLine, BOOK_TURNS},
+    protocol::Move,
+    referee::Referee,
+    simulator::normalize_vector,
+    state::{Drone, GameState, League, MAP_SIZE, MOVE_SPEED, SINK_SPEED},
+};
+
+// Move of a drone relative to its position: dx, dy and light
+type Displacement = (f64, f64, bool);
+// Moves of the lines found so far, by drone start
+type Lines = HashMap<(i32, i32), Vec<BookEntry>>;
+
+struct Options {
+    games: u64,
+    seed: u64,
+    depth: f64,
+    league: League,
+}
+
+fn parse_options(args: Vec<String>) -> Options {
+    let mut options = Options {
+        games: 20,
+        seed: 0,
+        depth: 4.0,
+        league: League::Bronze,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--games" => options.games = value.parse().unwrap(),
+            "--seed" => options.seed = value.parse().unwrap(),
+            "--depth" => options.depth = value.parse().unwrap(),
+            "--league" => options.league = League::parse(&value).unwrap(),
+            _ => panic!(
+                "unknown option {}, available options: --games --seed --depth --league",
+                arg
+            ),
+        }
+    }
+
+    options
+}
+
+// Start of the drone as the key of its line, and whether the line is mirrored for it.
+// Starts are rounded since mirrored ones can be a unit apart, as 3333 and 6666.
+fn line_key(start: (i32, i32)) -> ((i32, i32), bool) {
+    let is_mirrored = start.0 > MAP_SIZE / 2;
+    let x = BookLine::mirror_x(start.0, is_mirrored);
+    (((x + 5) / 10 * 10, start.1), is_mirrored)
+}
+
+// Move of the drone as a displacement of at most one turn, seen from the left half
+fn displacement(m: &Move, from: (i32, i32), is_mirrored: bool) -> Displacement {
+    let (dx, dy) = if m.should_move {
+        (
+            (m.x.unwrap() - from.0) as f64,
+            (m.y.unwrap() - from.1) as f64,
+        )
+    } else {
+        (0.0, SINK_SPEED)
+    };
+    let length = (dx.powf(2.0) + dy.powf(2.0)).sqrt();
+    let scale = if length > MOVE_SPEED {
+        MOVE_SPEED / length
+    } else {
+        1.0
+    };
+    let dx = if is_mirrored { -dx } else { dx };
+    (dx * scale, dy * scale, m.light)
+}
+
+// Moves chosen by the deep searches of both players at the given turn of a match,
+// every drone having played the lines of the book so far
+fn sample_turn(
+    seed: u64,
+    league: League,
+    params: Params,
+    lines: &Lines,
+    turn: usize,
+) -> Vec<((i32, i32), Displacement)> {
+    let mut referee = Referee::new(seed, league);
+    let mut bots = [0, 1].map(|_| {
+        let mut bot = GameState::new(&mut Cursor::new(referee.creatures_input()));
+        bot.params = params;
+        bot.league = league;
+        // The searches shuffle the moves, seeded so that the book can be generated again
+        bot.search_seed = Some(seed);
+        bot
+    });
+    let starts = referee.frames()[0]
+        .state
+        .my_drones
+        .values()
+        .chain(referee.frames()[0].state.their_drones.values())
+        .map(|drone| (drone.id, (drone.x, drone.y)))
+        .collect::<HashMap<_, _>>();
+
+    for played in 0..turn {
+        for (player, bot) in bots.iter_mut().enumerate() {
+            bot.update_state(&mut Cursor::new(referee.turn_input(player)));
+        }
+
+        let moves = starts
+            .iter()
+            .map(|(drone_id, start)| {
+                let (key, is_mirrored) = line_key(*start);
+                let entry = lines.get(&key).unwrap()[played];
+                (*drone_id, BookLine::to_move(entry, is_mirrored))
+            })
+            .collect();
+        referee.step(&moves);
+    }
+
+    let mut samples = Vec::new();
+    for (player, bot) in bots.iter_mut().enumerate() {
+        bot.update_state(&mut Cursor::new(referee.turn_input(player)));
+        for (drone_id, m, _) in bot.next_moves() {
+            let drone = referee.state().drone(drone_id);
+            let (key, is_mirrored) = line_key(*starts.get(&drone_id).unwrap());
+            let m = m.unwrap_or(Move {
+                should_move: false,
+                x: None,
+                y: None,
+                light: false,
+            });
+            samples.push((key, displacement(&m, (drone.x, drone.y), is_mirrored)));
+        }
+    }
+    samples
+}
+
+// Position of the drone of the line before the given turn
+fn line_position(key: (i32, i32), moves: &[BookEntry], turn: usize) -> (i32, i32) {
+    let mut drone = Drone {
+        id: 0,
+        x: key.0,
+        y: key.1,
+        emergency: 0,
+        battery: 0,
+        is_mine: true,
+    };
+    for entry in moves.iter().take(turn) {
+        drone.step(&BookLine::to_move(*entry, false));
+    }
+    (drone.x, drone.y)
+}
+
+fn format_table(lines: &Lines) -> String {
+    let mut keys = lines.keys().copied().collect::<Vec<_>>();
+    keys.sort();
+
+    let mut table = format!("pub const BOOK: [BookLine; {}] = [\n", keys.len());
+    for key in keys {
+        let moves = lines
+            .get(&key)
+            .unwrap()
+            .iter()
+            .map(|(x, y, light)| format!("({}, {}, {})", x, y, light))
+            .collect::<Vec<_>>();
+        table += &format!(
+            "    BookLine {{\n        start: ({}, {}),\n        moves: [{}],\n    }},\n",
+            key.0,
+            key.1,
+            moves.join(", ")
+        );
+    }
+    table + "];\n"
+}
+
+// Build the opening book one turn at a time: every drone plays the lines found so far,
+// then the move of the next turn is the average of the deep searches of both players
+// over the games, mirrored for the drones on the right half. Prints the table to paste
+// in opening.rs. The same options always give the same table.
+pub fn run(args: Vec<String>) {
+    let options = parse_options(args);
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let seeds = (0..options.games).map(|_| rng.gen()).collect::<Vec<u64>>();
+    let mut params = Params::load();
+    params.search_depth = options.depth;
+    params.book_turns = 0.0;
+    // Games are sampled in parallel, the searches must not depend on the time they get
+    params.search_time = 0.0;
+
+    let mut lines = Lines::new();
+    for turn in 0..BOOK_TURNS {
+        let samples = thread::scope(|scope| {
+            let handles = seeds
+                .iter()
+                .map(|seed| {
+                    let lines = &lines;
+                    scope.spawn(move || sample_turn(*seed, options.league, params, lines, turn))
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let mut totals: HashMap<(i32, i32), (f64, f64, usize, usize)> = HashMap::new();
+        for (key, (dx, dy, light)) in samples {
+            let total = totals.entry(key).or_default();
+            total.0 += dx;
+            total.1 += dy;
+            total.2 += light as usize;
+            total.3 += 1;
+        }
+
+        // A full move in the average direction, or sinking when the searches cancel out
+        for (key, (dx, dy, lights, count)) in totals {
+            let moves = lines.entry(key).or_default();
+            let (x, y) = line_position(key, moves, turn);
+            let (dx, dy) = if (dx.powf(2.0) + dy.powf(2.0)).sqrt() < count as f64 {
+                (0.0, SINK_SPEED)
+            } else {
+                let (normalized_x, normalized_y) = normalize_vector(dx, dy);
+                (normalized_x * MOVE_SPEED, normalized_y * MOVE_SPEED)
+            };
+            let target = (x + dx.round() as i32, y + dy.round() as i32);
+            moves.push((target.0, target.1, 2 * lights > count));
+        }
+        eprintln!("turn {} of {} done", turn + 1, BOOK_TURNS);
+    }
+
+    print!("{}", format_table(&lines));
+}
@@ -0,0 +1,486 @@
+// Evaluation of a state and its tunable parameters
+
+use std::{collections::HashSet, env, fs};
+
+use crate::{
+    opening::BOOK_TURNS,
+    state::{
+        Color, Creature, CreatureKind, Drone, GameState, Outcome, PlayerScans, COLOR_COMBO_POINTS,
+        LIGHT_BASE_RADIUS, MAX_TURNS, MOVE_SPEED, SURFACE_Y, TYPE_COMBO_POINTS,
+    },
+};
+
+pub const TERMINAL_SCORE: f64 = 1_000_000_000.0;
+
+// Evaluation and search parameters. The defaults are the ones compiled in for submission,
+// local runs can override them with a config file (FALL2023_PARAMS_FILE)
+// or a string (FALL2023_PARAMS) such as "score_weight=90000,combo_weight=800"
+#[derive(Clone, Copy, Debug)]
+pub struct Params {
+    pub score_weight: f64,
+    pub combo_weight: f64,
+    pub distance_scale: f64,
+    pub distance_log_base: f64,
+    pub distance_offset: f64,
+    pub distance_shift: f64,
+    // Weight of a point still reachable, discounted by the turns needed to get it
+    pub potential_weight: f64,
+    pub potential_discount: f64,
+    // Rounded to the closest integer
+    pub search_depth: f64,
+    // Milliseconds the searches of a turn may take, out of the 50 ms given by CodinGame.
+    // Iterative deepening stops there with the deepest search finished, 0 for no limit.
+    pub search_time: f64,
+    // First turns played from the opening book, rounded as well
+    pub book_turns: f64,
+    // When positive, the value network's estimate times this weight replaces
+    // the hand-written evaluation of states that are not over
+    pub value_net_weight: f64,
+}
+impl Default for Params {
+    fn default() -> Params {
+        Params {
+            score_weight: 100000.0,
+            combo_weight: 500.0,
+            distance_scale: 1500.0,
+            distance_log_base: 1.05,
+            distance_offset: 1.0,
+            distance_shift: -1500.0,
+            potential_weight: 10000.0,
+            potential_discount: 0.95,
+            search_depth: 3.0,
+            search_time: 40.0,
+            book_turns: BOOK_TURNS as f64,
+            value_net_weight: 0.0,
+        }
+    }
+}
+impl Params {
+    pub const NAMES: [&'static str; 12] = [
+        "score_weight",
+        "combo_weight",
+        "distance_scale",
+        "distance_log_base",
+        "distance_offset",
+        "distance_shift",
+        "potential_weight",
+        "potential_discount",
+        "search_depth",
+        "search_time",
+        "book_turns",
+        "value_net_weight",
+    ];
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        match name {
+            "score_weight" => Some(self.score_weight),
+            "combo_weight" => Some(self.combo_weight),
+            "distance_scale" => Some(self.distance_scale),
+            "distance_log_base" => Some(self.distance_log_base),
+            "distance_offset" => Some(self.distance_offset),
+            "distance_shift" => Some(self.distance_shift),
+            "potential_weight" => Some(self.potential_weight),
+            "potential_discount" => Some(self.potential_discount),
+            "search_depth" => Some(self.search_depth),
+            "search_time" => Some(self.search_time),
+            "book_turns" => Some(self.book_turns),
+            "value_net_weight" => Some(self.value_net_weight),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: f64) -> Result<(), String> {
+        match name {
+            "score_weight" => self.score_weight = value,
+            "combo_weight" => self.combo_weight = value,
+            "distance_scale" => self.distance_scale = value,
+            "distance_log_base" => self.distance_log_base = value,
+            "distance_offset" => self.distance_offset = value,
+            "distance_shift" => self.distance_shift = value,
+            "potential_weight" => self.potential_weight = value,
+            "potential_discount" => self.potential_discount = value,
+            "search_depth" => self.search_depth = value,
+            "search_time" => self.search_time = value,
+            "book_turns" => self.book_turns = value,
+            "value_net_weight" => self.value_net_weight = value,
+            _ => return Err(format!("unknown parameter `{}`", name)),
+        }
+        Ok(())
+    }
+
+    // Entries are `key=value`, separated by commas or new lines, `#` starts a comment
+    pub fn parse(config: &str) -> Result<Params, String> {
+        let mut params = Params::default();
+
+        for entry in config
+            .lines()
+            .flat_map(|line| line.split('#').next().unwrap().split(','))
+        {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or(format!("expected key=value, got `{}`", entry))?;
+            let value = value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("invalid value for `{}`: `{}`", key.trim(), value.trim()))?;
+
+            params.set(key.trim(), value)?;
+        }
+
+        Ok(params)
+    }
+
+    // One `key=value` line per parameter, readable by `parse`
+    pub fn to_config(self) -> String {
+        Params::NAMES
+            .iter()
+            .map(|name| format!("{}={}\n", name, self.get(name).unwrap()))
+            .collect()
+    }
+
+    pub fn load() -> Params {
+        let mut config = String::new();
+        if let Ok(path) = env::var("FALL2023_PARAMS_FILE") {
+            config += &fs::read_to_string(&path).expect("cannot read FALL2023_PARAMS_FILE");
+            config += "\n";
+        }
+        if let Ok(params) = env::var("FALL2023_PARAMS") {
+            config += &params;
+        }
+
+        Params::parse(&config).unwrap_or_else(|error| panic!("invalid parameters: {}", error))
+    }
+}
+
+pub fn emphasize_value(x: f64, params: &Params) -> f64 {
+    params.distance_scale * (x + params.distance_offset).log(params.distance_log_base)
+        + params.distance_shift
+}
+
+// Outcome of the race to the surface between both players' unsaved scans
+#[derive(Clone, Debug)]
+pub struct RaceReport {
+    pub my_turns_to_surface: i32,
+    pub foe_turns_to_surface: i32,
+    pub my_unsaved_count: usize,
+    pub foe_unsaved_count: usize,
+    // Points (mine, foe) depending on who saves first
+    pub points_if_first: (i32, i32),
+    pub points_if_same_turn: (i32, i32),
+    pub points_if_second: (i32, i32),
+}
+impl RaceReport {
+    // Points (mine, foe) if our scans reach the surface after the given number of turns,
+    // the foe going up as fast as it can
+    pub fn points_arriving_in(&self, turns: i32) -> (i32, i32) {
+        if self.foe_unsaved_count == 0 || turns < self.foe_turns_to_surface {
+            self.points_if_first
+        } else if turns == self.foe_turns_to_surface {
+            self.points_if_same_turn
+        } else {
+            self.points_if_second
+        }
+    }
+
+    // Points (mine, foe) if we go up right away
+    pub fn points_now(&self) -> (i32, i32) {
+        self.points_arriving_in(self.my_turns_to_surface)
+    }
+
+    // Score difference gained by surfacing now rather than staying down one more turn,
+    // during which the foe may get to the surface first and take the bonuses
+    pub fn swing(&self) -> i32 {
+        let now = self.points_now();
+        let later = self.points_arriving_in(self.my_turns_to_surface + 1);
+        (now.0 - now.1) - (later.0 - later.1)
+    }
+
+    pub fn should_surface(&self) -> bool {
+        self.my_unsaved_count > 0 && self.swing() > 0
+    }
+}
+
+impl GameState {
+    // Each term of the evaluation is pushed to `terms` when given, for tracing
+    pub fn evaluate(&self, terms: Option<&mut Vec<(&'static str, f64)>>) -> f64 {
+        if let Some(outcome) = self.outcome() {
+            let (my_final_score, foe_final_score) = self.final_scores();
+            let outcome_score = match outcome {
+                Outcome::Win => TERMINAL_SCORE,
+                Outcome::Loss => -TERMINAL_SCORE,
+                Outcome::Draw => 0.0,
+            };
+            let score_difference =
+                (my_final_score - foe_final_score) as f64 * self.params.score_weight;
+            if let Some(terms) = terms {
+                terms.extend([("outcome", outcome_score), ("score", score_difference)]);
+            }
+            return outcome_score + score_difference;
+        }
+
+        // Worked out once for the node, and shared by the value network and the terms below
+        let my_scans = self.player_scans(true);
+        let foe_scans = self.player_scans(false);
+
+        if self.params.value_net_weight > 0.0 {
+            let value = self.value_net_estimate(&my_scans, &foe_scans) as f64
+                * self.params.value_net_weight;
+            if let Some(terms) = terms {
+                terms.push(("value_net", value));
+            }
+            return value;
+        }
+
+        let score_difference = (self.my_score - self.foe_score) as f64 * self.params.score_weight;
+
+        let mut combos = 0.0;
+        combos += if self.was_all_colors_achieved {
+            self.params.combo_weight
+        } else {
+            0.0
+        };
+
+        combos += if self.was_1_of_each_achieved {
+            self.params.combo_weight
+        } else {
+            0.0
+        };
+
+        let creatures_in_game_count =
+            usize::max(1, self.creatures.len() - self.departed_creatures.len()) as f64;
+
+        // Each drone only goes after the fish assigned to it
+        let my_unscanned = my_scans.unscanned.iter().copied().collect::<HashSet<_>>();
+        // Summed per drone first, so that the total does not depend on the order of the drones
+        let avg_distance_from_creatures_not_scanned = self
+            .my_drones
+            .values()
+            .map(|my_drone| {
+                let targets = match self.assignment.routes.get(&my_drone.id) {
+                    Some(route) => route.as_slice(),
+                    None => my_scans.unscanned.as_slice(),
+                };
+
+                targets
+                    .iter()
+                    .filter(|creature_id| my_unscanned.contains(creature_id))
+                    .map(|creature_id| {
+                        let (x, y) = self.estimated_position(*creature_id, my_drone.x as f64);
+                        my_drone.distance_from(x, y)
+                    })
+                    .sum::<f64>()
+            })
+            .sum::<f64>()
+            / creatures_in_game_count;
+
+        let emphasized_avg_distance_from_creatures_not_scanned =
+            emphasize_value(avg_distance_from_creatures_not_scanned, &self.params);
+
+        // Their routes are unknown, so each of their drones counts every fish left to them
+        let foe_avg_distance_from_creatures_not_scanned = self
+            .their_drones
+            .values()
+            .map(|foe_drone| {
+                foe_scans
+                    .unscanned
+                    .iter()
+                    .map(|creature_id| {
+                        let (x, y) = self.estimated_position(*creature_id, foe_drone.x as f64);
+                        foe_drone.distance_from(x, y)
+                    })
+                    .sum::<f64>()
+            })
+            .sum::<f64>()
+            / creatures_in_game_count;
+
+        let foe_emphasized_avg_distance_from_creatures_not_scanned =
+            emphasize_value(foe_avg_distance_from_creatures_not_scanned, &self.params);
+
+        let potential = (self.potential_score_for(true, &my_scans, &foe_scans.saved)
+            - self.potential_score_for(false, &foe_scans, &my_scans.saved))
+            * self.params.potential_weight;
+
+        let evaluation = [
+            ("score", score_difference),
+            ("combos", combos),
+            (
+                "distance",
+                -emphasized_avg_distance_from_creatures_not_scanned,
+            ),
+            (
+                "foe_distance",
+                foe_emphasized_avg_distance_from_creatures_not_scanned,
+            ),
+            ("potential", potential),
+        ];
+        if let Some(terms) = terms {
+            terms.extend(evaluation);
+        }
+        evaluation.iter().map(|(_, value)| value).sum()
+    }
+
+    // Estimated number of turns to save the fish with the drones and their unsaved scans:
+    // the time to bring it to the surface when it is already scanned, otherwise the time
+    // to get in scan range with the closest drone and then go up from the fish's depth
+    pub fn turns_to_save(drones: &[(&Drone, &HashSet<i32>)], creature: &Creature) -> f64 {
+        drones
+            .iter()
+            .map(|(drone, unsaved)| {
+                if unsaved.contains(&creature.id) {
+                    return drone.turns_to_surface() as f64;
+                }
+
+                let (min_y, max_y) = creature.habitat();
+                let x = creature.x.unwrap_or(drone.x) as f64;
+                let y = creature.y.unwrap_or((min_y + max_y) / 2) as f64;
+                let distance = f64::max(0.0, drone.distance_from(x, y) - LIGHT_BASE_RADIUS);
+                distance / MOVE_SPEED + f64::max(0.0, y - SURFACE_Y) / MOVE_SPEED
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    // Points the player can still earn: fish still on the map, combos that can still be completed
+    // and first save bonuses the opponent has not taken yet, each one discounted by
+    // the estimated number of turns to get it
+    pub fn potential_score_for(
+        &self,
+        is_mine: bool,
+        scans: &PlayerScans,
+        opponent_saved: &HashSet<i32>,
+    ) -> f64 {
+        let saved = &scans.saved;
+        let turns_left = (MAX_TURNS - self.turn) as f64;
+        let drones = self
+            .drones_for(is_mine)
+            .values()
+            .map(|drone| (drone, scans.carried.get(&drone.id).unwrap()))
+            .collect::<Vec<_>>();
+
+        // Each fish with whether either player saved it and the turns to save it,
+        // None when it is saved, gone or out of reach before the end of the game.
+        // Combos are then checked on this list rather than through the sets, in id order
+        // so that the sum does not depend on the order of the map.
+        let mut fish = self
+            .creatures
+            .values()
+            .filter(|creature| creature.kind.is_fish())
+            .map(|creature| {
+                let is_saved = saved.contains(&creature.id);
+                let turns = if is_saved || self.departed_creatures.contains(&creature.id) {
+                    None
+                } else {
+                    Some(GameState::turns_to_save(&drones, creature))
+                        .filter(|turns| *turns <= turns_left)
+                };
+                (
+                    creature,
+                    is_saved,
+                    opponent_saved.contains(&creature.id),
+                    turns,
+                )
+            })
+            .collect::<Vec<_>>();
+        fish.sort_unstable_by_key(|(creature, ..)| creature.id);
+
+        let discounted =
+            |points: i32, turns: f64| points as f64 * self.params.potential_discount.powf(turns);
+        let mut potential = 0.0;
+
+        for (creature, _, is_opponent_saved, turns) in fish.iter() {
+            if let Some(turns) = turns {
+                let creature_score = creature.get_score();
+                let points = if *is_opponent_saved || !self.league.has_bonuses() {
+                    creature_score
+                } else {
+                    creature_score * 2
+                };
+                potential += discounted(points, *turns);
+            }
+        }
+
+        // Same scoring as the saves themselves, without combos below the leagues with bonuses
+        if !self.league.has_bonuses() {
+            return potential;
+        }
+
+        let combos = Color::ALL
+            .map(|color| (Some(color), None, COLOR_COMBO_POINTS))
+            .into_iter()
+            .chain(CreatureKind::FISH.map(|kind| (None, Some(kind), TYPE_COMBO_POINTS)));
+        for (color, kind, combo_points) in combos {
+            let members = || {
+                fish.iter().filter(|(creature, ..)| {
+                    color.is_none_or(|color| creature.color == Some(color))
+                        && kind.is_none_or(|kind| creature.kind == kind)
+                })
+            };
+            if members().all(|(_, is_saved, ..)| *is_saved) {
+                continue;
+            }
+
+            // The combo takes as long as its last fish, and cannot be completed anymore
+            // when one of them is out of reach
+            let turns = members()
+                .filter(|(_, is_saved, ..)| !is_saved)
+                .try_fold(0.0, |acc, (.., turns)| Some(f64::max(acc, (*turns)?)));
+            if let Some(turns) = turns {
+                let is_first = !members().all(|(_, _, is_opponent_saved, _)| *is_opponent_saved);
+                let points = combo_points * if is_first { 2 } else { 1 };
+                potential += discounted(points, turns);
+            }
+        }
+
+        potential
+    }
+
+    // Earliest number of turns for the player to bring all its unsaved scans to the surface
+    pub fn turns_to_surface_for(&self, is_mine: bool) -> i32 {
+        let saved = self.saved_creatures_for(is_mine);
+        self.drones_for(is_mine)
+            .values()
+            .filter(|drone| {
+                !self
+                    .unsaved_creatures_for_drone(drone.id, &saved)
+                    .is_empty()
+            })
+            .map(|drone| drone.turns_to_surface())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn race_report(&self) -> RaceReport {
+        let my_saved = self.saved_creatures_for(true);
+        let foe_saved = self.saved_creatures_for(false);
+        let my_unsaved = self.unsaved_creatures_for(true);
+        let foe_unsaved = self.unsaved_creatures_for(false);
+        let my_turns_to_surface = self.turns_to_surface_for(true);
+        let foe_turns_to_surface = self.turns_to_surface_for(false);
+
+        let my_saved_after: HashSet<i32> = my_saved.union(&my_unsaved).copied().collect();
+        let foe_saved_after: HashSet<i32> = foe_saved.union(&foe_unsaved).copied().collect();
+
+        RaceReport {
+            my_turns_to_surface,
+            foe_turns_to_surface,
+            my_unsaved_count: my_unsaved.len(),
+            foe_unsaved_count: foe_unsaved.len(),
+            points_if_first: (
+                self.points_for_save(&my_saved, &my_unsaved, &foe_saved),
+                self.points_for_save(&foe_saved, &foe_unsaved, &my_saved_after),
+            ),
+            points_if_same_turn: (
+                self.points_for_save(&my_saved, &my_unsaved, &foe_saved),
+                self.points_for_save(&foe_saved, &foe_unsaved, &my_saved),
+            ),
+            points_if_second: (
+                self.points_for_save(&my_saved, &my_unsaved, &foe_saved_after),
+                self.points_for_save(&foe_saved, &foe_unsaved, &my_saved),
+            ),
+        }
+    }
+}
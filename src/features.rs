@@ -0,0 +1,178 @@
+// Fixed-length numeric description of a state from my side, the input of the value network
+
+use std::collections::HashSet;
+
+use crate::state::{
+    Color, CreatureKind, Drone, GameState, PlayerScans, MAP_SIZE, MAX_BATTERY, MAX_TURNS,
+};
+
+// Mine first then the foe's: score, points of the unsaved scans, share of each color
+// and each type saved, then for each drone its depth, battery, emergency, distance to the
+// closest fish left to scan of each type and to the closest monster. The turn comes last.
+// The layout only grows at the end, and the value network must be trained again when it does.
+pub const SCORE: usize = 0;
+pub const UNSAVED_POINTS: usize = 1;
+pub const COLOR_SAVED: usize = 2;
+const COLOR_SAVED_COUNT: usize = Color::ALL.len();
+pub const KIND_SAVED: usize = COLOR_SAVED + COLOR_SAVED_COUNT;
+pub const DRONES: usize = KIND_SAVED + CreatureKind::FISH.len();
+
+// Offsets within a drone
+pub const DEPTH: usize = 0;
+pub const BATTERY: usize = 1;
+pub const EMERGENCY: usize = 2;
+pub const FISH_DISTANCE: usize = 3;
+pub const MONSTER_DISTANCE: usize = FISH_DISTANCE + CreatureKind::FISH.len();
+
+// Drones described for each player, missing ones are left at their default features
+pub const DRONE_SLOTS: usize = 2;
+pub const DRONE_FEATURE_COUNT: usize = MONSTER_DISTANCE + 1;
+pub const PLAYER_FEATURE_COUNT: usize = DRONES + DRONE_SLOTS * DRONE_FEATURE_COUNT;
+pub const TURN: usize = 2 * PLAYER_FEATURE_COUNT;
+pub const FEATURE_COUNT: usize = TURN + 1;
+// Scores and points are divided by this to stay around 1
+const POINTS_SCALE: f32 = 100.0;
+
+// Index of a player feature, given its offset above
+pub fn player_index(is_mine: bool, offset: usize) -> usize {
+    if is_mine {
+        offset
+    } else {
+        PLAYER_FEATURE_COUNT + offset
+    }
+}
+
+// Index of a feature of the drone in the given slot, drones being in id order
+pub fn drone_index(is_mine: bool, slot: usize, offset: usize) -> usize {
+    player_index(is_mine, DRONES + slot * DRONE_FEATURE_COUNT + offset)
+}
+
+// Name of every feature in order, as `my_drone0_type1_distance`
+pub fn feature_names() -> Vec<String> {
+    let mut names = vec![String::new(); FEATURE_COUNT];
+    for (is_mine, player) in [(true, "my"), (false, "foe")] {
+        let mut name = |offset: usize, name: String| {
+            names[player_index(is_mine, offset)] = format!("{}_{}", player, name)
+        };
+        name(SCORE, "score".to_string());
+        name(UNSAVED_POINTS, "unsaved_points".to_string());
+        for (i, color) in Color::ALL.iter().enumerate() {
+            name(COLOR_SAVED + i, format!("{:?}_saved", color).to_lowercase());
+        }
+        for (i, kind) in CreatureKind::FISH.iter().enumerate() {
+            name(KIND_SAVED + i, format!("{:?}_saved", kind).to_lowercase());
+        }
+
+        for slot in 0..DRONE_SLOTS {
+            let drone = DRONES + slot * DRONE_FEATURE_COUNT;
+            name(drone + DEPTH, format!("drone{}_depth", slot));
+            name(drone + BATTERY, format!("drone{}_battery", slot));
+            name(drone + EMERGENCY, format!("drone{}_emergency", slot));
+            for (i, kind) in CreatureKind::FISH.iter().enumerate() {
+                let kind = format!("{:?}", kind).to_lowercase();
+                name(
+                    drone + FISH_DISTANCE + i,
+                    format!("drone{}_{}_distance", slot, kind),
+                );
+            }
+            name(
+                drone + MONSTER_DISTANCE,
+                format!("drone{}_monster_distance", slot),
+            );
+        }
+    }
+    names[TURN] = "turn".to_string();
+    names
+}
+
+impl GameState {
+    pub fn features(&self) -> [f32; FEATURE_COUNT] {
+        self.features_from(&self.player_scans(true), &self.player_scans(false))
+    }
+
+    // Features from the scans of both players, when they are already worked out
+    pub fn features_from(
+        &self,
+        my_scans: &PlayerScans,
+        foe_scans: &PlayerScans,
+    ) -> [f32; FEATURE_COUNT] {
+        let mut features = [0.0; FEATURE_COUNT];
+        self.player_features(true, my_scans, &foe_scans.saved, &mut features);
+        self.player_features(false, foe_scans, &my_scans.saved, &mut features);
+        features[TURN] = self.turn as f32 / MAX_TURNS as f32;
+        features
+    }
+
+    fn player_features(
+        &self,
+        is_mine: bool,
+        scans: &PlayerScans,
+        opponent_saved: &HashSet<i32>,
+        features: &mut [f32; FEATURE_COUNT],
+    ) {
+        let score = if is_mine {
+            self.my_score
+        } else {
+            self.foe_score
+        };
+        features[player_index(is_mine, SCORE)] = score as f32 / POINTS_SCALE;
+        features[player_index(is_mine, UNSAVED_POINTS)] =
+            self.points_for_save(&scans.saved, &scans.unsaved, opponent_saved) as f32
+                / POINTS_SCALE;
+
+        // Fish (saved, total) of each color then of each kind
+        let mut counts = [(0, 0); COLOR_SAVED_COUNT + CreatureKind::FISH.len()];
+        for creature in self.creatures.values() {
+            let Some(color) = creature.color else {
+                continue;
+            };
+            let is_saved = scans.saved.contains(&creature.id) as usize;
+            for group in [
+                color.id() as usize,
+                COLOR_SAVED_COUNT + creature.kind.id() as usize,
+            ] {
+                counts[group].0 += is_saved;
+                counts[group].1 += 1;
+            }
+        }
+        for (i, (saved_count, count)) in counts.into_iter().enumerate() {
+            features[player_index(is_mine, COLOR_SAVED + i)] =
+                saved_count as f32 / usize::max(1, count) as f32;
+        }
+
+        let mut drones = self.drones_for(is_mine).values().collect::<Vec<_>>();
+        drones.sort_by_key(|drone| drone.id);
+        for slot in 0..DRONE_SLOTS {
+            let index = drone_index(is_mine, slot, 0);
+            let drone_features = &mut features[index..index + DRONE_FEATURE_COUNT];
+            match drones.get(slot) {
+                Some(drone) => self.drone_features(drone, &scans.unscanned, drone_features),
+                None => drone_features[FISH_DISTANCE..].fill(1.0),
+            }
+        }
+    }
+
+    // Distances are divided by the map size and capped at 1, which also stands for none
+    fn drone_features(&self, drone: &Drone, unscanned: &[i32], features: &mut [f32]) {
+        features[DEPTH] = drone.y as f32 / MAP_SIZE as f32;
+        features[BATTERY] = drone.battery as f32 / MAX_BATTERY as f32;
+        features[EMERGENCY] = drone.emergency as f32;
+
+        let distance_to =
+            |x: f64, y: f64| f64::min(1.0, drone.distance_from(x, y) / MAP_SIZE as f64) as f32;
+        features[FISH_DISTANCE..MONSTER_DISTANCE].fill(1.0);
+        for creature_id in unscanned {
+            let kind = self.creatures.get(creature_id).unwrap().kind;
+            let (x, y) = self.estimated_position(*creature_id, drone.x as f64);
+            let closest = &mut features[FISH_DISTANCE + kind.id() as usize];
+            *closest = f32::min(*closest, distance_to(x, y));
+        }
+
+        features[MONSTER_DISTANCE] = self
+            .creatures
+            .values()
+            .filter(|creature| !creature.kind.is_fish())
+            .filter_map(|creature| Some(distance_to(creature.x? as f64, creature.y? as f64)))
+            .fold(1.0, f32::min);
+    }
+}
@@ -0,0 +1,47 @@
+// Bot for the Fall Challenge 2023, Seabed Security.
+// CodinGame takes a single source file: `cargo run --bin bundle` inlines these modules
+// along with main.rs into one submission file.
+
+macro_rules! parse_input {
+    ($x:expr, $t:ident) => {
+        $x.trim().parse::<$t>().unwrap()
+    };
+}
+
+// Ideas
+
+// Scoring heuristic based on the game description
+
+// Minimax as it's a zero sum game
+// Alpha beta pruning to reduce the number of nodes to explore
+// To use when the strategy should be deterministic
+
+// MCTS could be useful because of the number of possible states
+// Relevant because of the randomness in which fishes move
+// To use when the strategy should be stochastic
+
+pub mod eval;
+pub mod features;
+pub mod opening;
+pub mod protocol;
+pub mod search;
+pub mod simulator;
+pub mod state;
+pub mod strategy;
+pub mod trace;
+pub mod tracker;
+pub mod value_net;
+
+// Local tooling, left out of the CodinGame submission
+#[cfg(feature = "local")]
+pub mod ascii;
+#[cfg(feature = "local")]
+pub mod book;
+#[cfg(feature = "local")]
+pub mod referee;
+#[cfg(feature = "local")]
+pub mod replay;
+#[cfg(feature = "local")]
+pub mod trainer;
+#[cfg(feature = "local")]
+pub mod tuner;
@@ -0,0 +1,102 @@
+// Opening book: the first moves of the drones are the same on every map, since they start
+// at known positions and fish stay in known bands. The table is written by `book`.
+
+use crate::{
+    protocol::Move,
+    state::{GameState, MAP_SIZE},
+};
+
+pub const BOOK_TURNS: usize = 6;
+// Distance from the position expected by the book within which a drone still follows it
+pub const BOOK_TOLERANCE: i32 = 50;
+
+// Target and light of a turn
+pub type BookEntry = (i32, i32, bool);
+
+// Line of a drone starting on the left half of the map, with the target and light of each
+// turn. Drones starting on the right half play the mirrored line.
+#[derive(Clone, Copy, Debug)]
+pub struct BookLine {
+    pub start: (i32, i32),
+    pub moves: [BookEntry; BOOK_TURNS],
+}
+impl BookLine {
+    pub fn mirror_x(x: i32, is_mirrored: bool) -> i32 {
+        if is_mirrored {
+            MAP_SIZE - x
+        } else {
+            x
+        }
+    }
+
+    pub fn to_move(entry: BookEntry, is_mirrored: bool) -> Move {
+        let (x, y, light) = entry;
+        Move {
+            should_move: true,
+            x: Some(BookLine::mirror_x(x, is_mirrored)),
+            y: Some(y),
+            light,
+        }
+    }
+
+    pub fn book_move(&self, turn: usize, is_mirrored: bool) -> Move {
+        BookLine::to_move(self.moves[turn], is_mirrored)
+    }
+}
+
+// Written by `cargo run --release -- book --games 20 --depth 4 --seed 0` with the
+// default parameters (no FALL2023_PARAMS), which gives this table again
+pub const BOOK: [BookLine; 2] = [
+    BookLine {
+        start: (2000, 500),
+        moves: [
+            (1754, 1047, false),
+            (1531, 1604, false),
+            (1308, 2161, true),
+            (1345, 2760, false),
+            (1468, 3346, false),
+            (1474, 3946, false),
+        ],
+    },
+    BookLine {
+        start: (3330, 500),
+        moves: [
+            (3576, 1047, false),
+            (3748, 1622, false),
+            (3893, 2203, false),
+            (3892, 2802, false),
+            (3890, 3402, false),
+            (3797, 3994, false),
+        ],
+    },
+];
+
+impl GameState {
+    // Move of the book for the drone, as long as it followed its line until now
+    pub fn book_move(&self, drone_id: i32) -> Option<Move> {
+        let turn = (self.turn - 1) as usize;
+        let book_turns = usize::min(BOOK_TURNS, self.params.book_turns.round() as usize);
+        if turn >= book_turns {
+            return None;
+        }
+        let drone = self.my_drones.get(&drone_id)?;
+
+        for line in BOOK.iter() {
+            for is_mirrored in [false, true] {
+                let mut expected = drone.clone();
+                expected.x = BookLine::mirror_x(line.start.0, is_mirrored);
+                expected.y = line.start.1;
+                for played in 0..turn {
+                    expected.step(&line.book_move(played, is_mirrored));
+                }
+
+                if (expected.x - drone.x).abs() <= BOOK_TOLERANCE
+                    && (expected.y - drone.y).abs() <= BOOK_TOLERANCE
+                {
+                    return Some(line.book_move(turn, is_mirrored));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,345 @@
+// Game protocol: turn input parsed into the state, moves formatted into actions
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::BufRead,
+};
+
+use crate::{
+    eval::Params,
+    state::{Color, Creature, CreatureKind, Drone, GameState, League},
+    strategy::Assignment,
+    trace::TraceLevel,
+    tracker::Radar,
+};
+
+#[derive(Clone, Debug)]
+pub struct Move {
+    pub should_move: bool,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub light: bool,
+}
+impl Move {
+    // Action line without the debug message
+    pub fn to_action(&self) -> String {
+        let light = if self.light { "1" } else { "0" };
+
+        if self.should_move {
+            format!("MOVE {} {} {}", self.x.unwrap(), self.y.unwrap(), light)
+        } else {
+            format!("WAIT {}", light)
+        }
+    }
+
+    // Action line as written by a bot, anything after the light is its debug message
+    pub fn parse(action: &str) -> Result<Move, String> {
+        let invalid = || format!("invalid action `{}`", action);
+        let parse_light = |light: Option<&str>| match light {
+            Some("0") => Ok(false),
+            Some("1") => Ok(true),
+            _ => Err(invalid()),
+        };
+        let parse_coordinate = |value: Option<&str>| {
+            value
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(invalid)
+        };
+
+        let mut words = action.split_whitespace();
+        match words.next() {
+            Some("MOVE") => Ok(Move {
+                should_move: true,
+                x: Some(parse_coordinate(words.next())?),
+                y: Some(parse_coordinate(words.next())?),
+                light: parse_light(words.next())?,
+            }),
+            Some("WAIT") => Ok(Move {
+                should_move: false,
+                x: None,
+                y: None,
+                light: parse_light(words.next())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// Quadrant of a creature relative to a drone, as reported by the radar
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RadarDir {
+    TL,
+    TR,
+    BL,
+    BR,
+}
+impl RadarDir {
+    pub fn parse(value: &str) -> Result<RadarDir, String> {
+        match value {
+            "TL" => Ok(RadarDir::TL),
+            "TR" => Ok(RadarDir::TR),
+            "BL" => Ok(RadarDir::BL),
+            "BR" => Ok(RadarDir::BR),
+            _ => Err(format!("invalid radar direction `{}`", value)),
+        }
+    }
+
+    // Direction of `to` seen from `from`, creatures on the same line count as bottom or right
+    pub fn between(from: (i32, i32), to: (i32, i32)) -> RadarDir {
+        match (to.1 < from.1, to.0 < from.0) {
+            (true, true) => RadarDir::TL,
+            (true, false) => RadarDir::TR,
+            (false, true) => RadarDir::BL,
+            (false, false) => RadarDir::BR,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RadarDir::TL => "TL",
+            RadarDir::TR => "TR",
+            RadarDir::BL => "BL",
+            RadarDir::BR => "BR",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RadarBlip {
+    pub drone_id: i32,
+    pub creature_id: i32,
+    pub radar: RadarDir,
+}
+
+impl GameState {
+    pub fn new(input: &mut impl BufRead) -> GameState {
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        let creature_count = parse_input!(input_line, i32);
+
+        let mut creatures = HashMap::new();
+        let my_drones = HashMap::new();
+        let their_drones = HashMap::new();
+        let scans = HashSet::new();
+        let drone_scans = HashSet::new();
+        let departed_creatures = HashSet::new();
+
+        for _i in 0..creature_count as usize {
+            let mut input_line = String::new();
+            input.read_line(&mut input_line).unwrap();
+            let inputs = input_line.split(" ").collect::<Vec<_>>();
+            let creature_id = parse_input!(inputs[0], i32);
+            let kind = CreatureKind::parse(parse_input!(inputs[2], i32)).unwrap();
+            let color = Color::parse(parse_input!(inputs[1], i32), kind).unwrap();
+            creatures.insert(
+                creature_id,
+                Creature {
+                    id: creature_id,
+                    color,
+                    x: None,
+                    y: None,
+                    vx: None,
+                    vy: None,
+                    kind,
+                    last_seen_turn: None,
+                },
+            );
+        }
+
+        let league = League::detect(&creatures);
+
+        GameState {
+            creatures,
+            turn: 0,
+            my_score: 0,
+            foe_score: 0,
+            my_scan_count: 0,
+            foe_scan_count: 0,
+            my_drone_count: league.drone_count(),
+            foe_drone_count: league.drone_count(),
+            my_drones,
+            their_drones,
+            radar: Radar::default(),
+            scans,
+            drone_scans,
+            departed_creatures,
+            league,
+            assignment: Assignment::default(),
+            is_racing: false,
+            params: Params::load(),
+            trace_level: TraceLevel::load(),
+            search_seed: None,
+            was_1_of_each_achieved: false,
+            was_all_colors_achieved: false,
+        }
+    }
+
+    pub fn update_state(&mut self, input: &mut impl BufRead) {
+        self.turn += 1;
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        self.my_score = parse_input!(input_line, i32);
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        self.foe_score = parse_input!(input_line, i32);
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        self.my_scan_count = parse_input!(input_line, i32);
+
+        for _i in 0..self.my_scan_count as usize {
+            let mut input_line = String::new();
+            input.read_line(&mut input_line).unwrap();
+
+            let creature_id = parse_input!(input_line, i32);
+            let my_drone_id = self.my_drones.values().next().unwrap().id;
+            self.scans.insert((my_drone_id, creature_id));
+        }
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        self.foe_scan_count = parse_input!(input_line, i32);
+
+        for _i in 0..self.foe_scan_count as usize {
+            let mut input_line = String::new();
+            input.read_line(&mut input_line).unwrap();
+
+            let creature_id = parse_input!(input_line, i32);
+            let foe_drone_id = self.their_drones.values().next().unwrap().id;
+            self.scans.insert((foe_drone_id, creature_id));
+        }
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        self.my_drone_count = parse_input!(input_line, i32);
+
+        for _i in 0..self.my_drone_count as usize {
+            let mut input_line = String::new();
+            input.read_line(&mut input_line).unwrap();
+            let inputs = input_line.split(" ").collect::<Vec<_>>();
+            let drone_id = parse_input!(inputs[0], i32);
+
+            let drone = Drone {
+                id: drone_id,
+                x: parse_input!(inputs[1], i32),
+                y: parse_input!(inputs[2], i32),
+                emergency: parse_input!(inputs[3], i32),
+                battery: parse_input!(inputs[4], i32),
+                is_mine: true,
+            };
+            self.my_drones.insert(drone_id, drone.clone());
+        }
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        self.foe_drone_count = parse_input!(input_line, i32);
+
+        for _i in 0..self.foe_drone_count as usize {
+            let mut input_line = String::new();
+            input.read_line(&mut input_line).unwrap();
+            let inputs = input_line.split(" ").collect::<Vec<_>>();
+            let drone_id = parse_input!(inputs[0], i32);
+
+            let drone = Drone {
+                id: drone_id,
+                x: parse_input!(inputs[1], i32),
+                y: parse_input!(inputs[2], i32),
+                emergency: parse_input!(inputs[3], i32),
+                battery: parse_input!(inputs[4], i32),
+                is_mine: false,
+            };
+            self.their_drones.insert(drone_id, drone.clone());
+        }
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        let drone_scan_count = parse_input!(input_line, i32);
+
+        // Scans carried by drones that were not saved yet
+        self.drone_scans.clear();
+        for _i in 0..drone_scan_count as usize {
+            let mut input_line = String::new();
+            input.read_line(&mut input_line).unwrap();
+            let inputs = input_line.split(" ").collect::<Vec<_>>();
+
+            let drone_id = parse_input!(inputs[0], i32);
+            let creature_id = parse_input!(inputs[1], i32);
+            self.drone_scans.insert((drone_id, creature_id));
+        }
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        let visible_creature_count = parse_input!(input_line, i32);
+
+        // Creatures out of sight keep moving at their last known speed
+        for creature in self.creatures.values_mut() {
+            if let (Some(x), Some(y), Some(vx), Some(vy)) =
+                (creature.x, creature.y, creature.vx, creature.vy)
+            {
+                let (min_y, max_y) = creature.habitat();
+                creature.x = Some(x + vx);
+                creature.y = Some((y + vy).clamp(min_y, max_y));
+            }
+        }
+
+        let mut visible_creature_ids = HashSet::new();
+        for _i in 0..visible_creature_count as usize {
+            let mut input_line = String::new();
+            input.read_line(&mut input_line).unwrap();
+            let inputs = input_line.split(" ").collect::<Vec<_>>();
+            let creature_id = parse_input!(inputs[0], i32);
+            visible_creature_ids.insert(creature_id);
+
+            self.creatures.insert(
+                creature_id,
+                Creature {
+                    id: creature_id,
+                    x: Some(parse_input!(inputs[1], i32)),
+                    y: Some(parse_input!(inputs[2], i32)),
+                    vx: Some(parse_input!(inputs[3], i32)),
+                    vy: Some(parse_input!(inputs[4], i32)),
+                    last_seen_turn: Some(self.turn),
+                    ..self.creatures.get(&creature_id).unwrap().clone()
+                },
+            );
+        }
+
+        let mut input_line = String::new();
+        input.read_line(&mut input_line).unwrap();
+        let radar_blip_count = parse_input!(input_line, i32);
+
+        self.radar.clear();
+        for _i in 0..radar_blip_count as usize {
+            let mut input_line = String::new();
+            input.read_line(&mut input_line).unwrap();
+            let inputs = input_line.split(" ").collect::<Vec<_>>();
+
+            self.radar.insert(RadarBlip {
+                drone_id: parse_input!(inputs[0], i32),
+                creature_id: parse_input!(inputs[1], i32),
+                radar: RadarDir::parse(inputs[2].trim()).unwrap(),
+            });
+        }
+
+        // The radar reports every creature still in the game, or without radar they are
+        // all visible, so a creature missing from both has left the map for good
+        let present_creature_ids = if self.league.has_radar() {
+            self.radar.creature_ids()
+        } else {
+            visible_creature_ids
+        };
+        if !present_creature_ids.is_empty() {
+            for creature_id in self.creatures.keys() {
+                if !present_creature_ids.contains(creature_id) {
+                    self.departed_creatures.insert(*creature_id);
+                }
+            }
+        }
+
+        self.forget_stale_creatures();
+        self.update_assignment();
+        self.update_race();
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    io::Cursor,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    eval::Params,
+    protocol::{Move, RadarDir},
+    state::{
+        Color, CreatureKind, Drone, GameState, League, Outcome, FISH_SPEED, LIGHT_BASE_RADIUS,
+        LIGHT_POWER_RADIUS, MAP_SIZE, MAX_BATTERY, MONSTER_SPEED,
+    },
+};
+
+const FIRST_CREATURE_ID: i32 = 4;
+const DRONE_START_Y: i32 = 500;
+// Start of player 0's and player 1's drones, as many as the league has
+const DRONE_START_XS: [(i32, i32); 2] = [(3333, 6666), (2000, 8000)];
+const MAX_MONSTER_COUNT: i32 = 6;
+
+// Local referee running a match between two bots.
+// The rules engine is the forward model of GameState with every creature known:
+// player 0 owns `my_drones` of the underlying state and player 1 `their_drones`.
+// Without the radar every creature is visible to both players, with it only
+// the creatures in the light of their drones are.
+pub struct Referee {
+    state: GameState,
+    frames: Vec<Frame>,
+}
+
+// State of the match at the end of a turn, for the replay
+pub struct Frame {
+    pub state: GameState,
+    // Drones that turned their light on during the turn
+    pub lights: HashSet<i32>,
+    // Move of each drone during the turn, empty for the initial state
+    pub moves: HashMap<i32, Move>,
+    // Debug message of each drone's action
+    pub messages: HashMap<i32, String>,
+    // What each player knew when choosing its moves, empty for the initial state
+    pub views: Vec<GameState>,
+    // Turn input each player received, empty for the initial state
+    pub inputs: Vec<String>,
+}
+impl Referee {
+    // Map generated from the seed, the same seed always gives the same map
+    pub fn new(seed: u64, league: League) -> Referee {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let monster_count = if league.has_monsters() {
+            rng.gen_range(1..=MAX_MONSTER_COUNT)
+        } else {
+            0
+        };
+        let fish_count = Color::ALL.len() as i32 * CreatureKind::FISH.len() as i32;
+
+        let mut creatures_input = format!("{}\n", fish_count + monster_count);
+        for color in Color::ALL {
+            for kind in CreatureKind::FISH {
+                let creature_id =
+                    FIRST_CREATURE_ID + color.id() * CreatureKind::FISH.len() as i32 + kind.id();
+                writeln!(
+                    creatures_input,
+                    "{} {} {}",
+                    creature_id,
+                    color.id(),
+                    kind.id()
+                )
+                .unwrap();
+            }
+        }
+        for i in 0..monster_count {
+            writeln!(
+                creatures_input,
+                "{} -1 -1",
+                FIRST_CREATURE_ID + fish_count + i
+            )
+            .unwrap();
+        }
+        let mut state = GameState::new(&mut Cursor::new(creatures_input));
+        state.league = league;
+
+        let mut creature_ids = state.creatures.keys().copied().collect::<Vec<_>>();
+        creature_ids.sort();
+        for creature_id in creature_ids {
+            let creature = state.creatures.get_mut(&creature_id).unwrap();
+            let (min_y, max_y) = creature.habitat();
+            let speed = if creature.kind.is_fish() {
+                FISH_SPEED
+            } else {
+                MONSTER_SPEED
+            };
+            let angle = rng.gen_range(0.0..std::f64::consts::TAU);
+            creature.x = Some(rng.gen_range(0..MAP_SIZE));
+            creature.y = Some(rng.gen_range(min_y..max_y));
+            creature.vx = Some((angle.cos() * speed).round() as i32);
+            creature.vy = Some((angle.sin() * speed).round() as i32);
+            creature.last_seen_turn = Some(state.turn);
+        }
+
+        let drone_count = league.drone_count() as usize;
+        for (i, (my_x, foe_x)) in DRONE_START_XS.iter().take(drone_count).enumerate() {
+            let my_drone_id = 2 * i as i32;
+            let foe_drone_id = 2 * i as i32 + 1;
+            state
+                .my_drones
+                .insert(my_drone_id, Referee::new_drone(my_drone_id, *my_x, true));
+            state.their_drones.insert(
+                foe_drone_id,
+                Referee::new_drone(foe_drone_id, *foe_x, false),
+            );
+        }
+
+        let frames = vec![Frame {
+            state: state.clone(),
+            lights: HashSet::new(),
+            moves: HashMap::new(),
+            messages: HashMap::new(),
+            views: Vec::new(),
+            inputs: Vec::new(),
+        }];
+        Referee { state, frames }
+    }
+
+    fn new_drone(id: i32, x: i32, is_mine: bool) -> Drone {
+        Drone {
+            id,
+            x,
+            y: DRONE_START_Y,
+            emergency: 0,
+            battery: MAX_BATTERY,
+            is_mine,
+        }
+    }
+
+    // Initialization input, the same for both players
+    pub fn creatures_input(&self) -> String {
+        let mut creature_ids = self.state.creatures.keys().copied().collect::<Vec<_>>();
+        creature_ids.sort();
+
+        let mut input = format!("{}\n", creature_ids.len());
+        for creature_id in creature_ids {
+            let creature = self.state.creatures.get(&creature_id).unwrap();
+            let color = creature.color.map_or(-1, Color::id);
+            writeln!(input, "{} {} {}", creature.id, color, creature.kind.id()).unwrap();
+        }
+        input
+    }
+
+    // Turn input as seen by the player
+    pub fn turn_input(&self, player: usize) -> String {
+        let is_mine = player == 0;
+        let state = &self.state;
+        let mut input = String::new();
+
+        let (my_score, foe_score) = if is_mine {
+            (state.my_score, state.foe_score)
+        } else {
+            (state.foe_score, state.my_score)
+        };
+        writeln!(input, "{}\n{}", my_score, foe_score).unwrap();
+
+        for side in [is_mine, !is_mine] {
+            let mut saved = state
+                .saved_creatures_for(side)
+                .into_iter()
+                .collect::<Vec<_>>();
+            saved.sort();
+            writeln!(input, "{}", saved.len()).unwrap();
+            for creature_id in saved {
+                writeln!(input, "{}", creature_id).unwrap();
+            }
+        }
+
+        for side in [is_mine, !is_mine] {
+            let mut drones = state.drones_for(side).values().collect::<Vec<_>>();
+            drones.sort_by_key(|drone| drone.id);
+            writeln!(input, "{}", drones.len()).unwrap();
+            for drone in drones {
+                writeln!(
+                    input,
+                    "{} {} {} {} {}",
+                    drone.id, drone.x, drone.y, drone.emergency, drone.battery
+                )
+                .unwrap();
+            }
+        }
+
+        let mut drone_scans = state.drone_scans.iter().copied().collect::<Vec<_>>();
+        drone_scans.sort();
+        writeln!(input, "{}", drone_scans.len()).unwrap();
+        for (drone_id, creature_id) in drone_scans {
+            writeln!(input, "{} {}", drone_id, creature_id).unwrap();
+        }
+
+        let mut creatures = state
+            .creatures
+            .values()
+            .filter(|creature| !state.departed_creatures.contains(&creature.id))
+            .collect::<Vec<_>>();
+        creatures.sort_by_key(|creature| creature.id);
+
+        let mut drones = state.drones_for(is_mine).values().collect::<Vec<_>>();
+        drones.sort_by_key(|drone| drone.id);
+        let lights = &self.frames.last().unwrap().lights;
+        let is_visible = |x: i32, y: i32| {
+            !state.league.has_radar()
+                || drones.iter().any(|drone| {
+                    let radius = if lights.contains(&drone.id) {
+                        LIGHT_POWER_RADIUS
+                    } else {
+                        LIGHT_BASE_RADIUS
+                    };
+                    drone.distance_from(x as f64, y as f64) <= radius
+                })
+        };
+        let visible_creatures = creatures
+            .iter()
+            .filter(|creature| is_visible(creature.x.unwrap(), creature.y.unwrap()))
+            .collect::<Vec<_>>();
+
+        writeln!(input, "{}", visible_creatures.len()).unwrap();
+        for creature in visible_creatures {
+            writeln!(
+                input,
+                "{} {} {} {} {}",
+                creature.id,
+                creature.x.unwrap(),
+                creature.y.unwrap(),
+                creature.vx.unwrap(),
+                creature.vy.unwrap()
+            )
+            .unwrap();
+        }
+
+        if !state.league.has_radar() {
+            writeln!(input, "0").unwrap();
+            return input;
+        }
+
+        writeln!(input, "{}", drones.len() * creatures.len()).unwrap();
+        for drone in drones {
+            for creature in creatures.iter() {
+                let radar = RadarDir::between(
+                    (drone.x, drone.y),
+                    (creature.x.unwrap(), creature.y.unwrap()),
+                );
+                writeln!(input, "{} {} {}", drone.id, creature.id, radar.as_str()).unwrap();
+            }
+        }
+
+        input
+    }
+
+    // Play the match to the end and return the outcome for player 0
+    pub fn play(&mut self, params: [Params; 2]) -> Outcome {
+        let mut bots = params.map(|params| {
+            let mut bot = GameState::new(&mut Cursor::new(self.creatures_input()));
+            bot.params = params;
+            bot.league = self.state.league;
+            bot
+        });
+
+        while !self.state.is_terminal() {
+            let mut moves = HashMap::new();
+            let mut messages = HashMap::new();
+            let mut views = Vec::new();
+            let mut inputs = Vec::new();
+            for (player, bot) in bots.iter_mut().enumerate() {
+                let input = self.turn_input(player);
+                bot.update_state(&mut Cursor::new(&input));
+                views.push(bot.clone());
+                inputs.push(input);
+
+                for (drone_id, m, message) in bot.next_moves() {
+                    messages.insert(drone_id, message);
+                    moves.insert(
+                        drone_id,
+                        m.unwrap_or(Move {
+                            should_move: false,
+                            x: None,
+                            y: None,
+                            light: false,
+                        }),
+                    );
+                }
+            }
+
+            self.step(&moves);
+            let frame = self.frames.last_mut().unwrap();
+            frame.messages = messages;
+            frame.views = views;
+            frame.inputs = inputs;
+        }
+
+        self.state.outcome().unwrap()
+    }
+
+    // Play one turn of the rules with the given moves, drones without a move stay where they are
+    pub fn step(&mut self, moves: &HashMap<i32, Move>) {
+        let batteries = self.batteries();
+        self.state.apply_moves(moves);
+        // The referee sees every creature, so that the rules apply to their exact positions
+        for creature in self.state.creatures.values_mut() {
+            creature.last_seen_turn = Some(self.state.turn);
+        }
+
+        // The battery only goes down when the light was on
+        let after = self.batteries();
+        let lights = batteries
+            .into_iter()
+            .filter(|(drone_id, battery)| after.get(drone_id).unwrap() < battery)
+            .map(|(drone_id, _)| drone_id)
+            .collect();
+        self.frames.push(Frame {
+            state: self.state.clone(),
+            lights,
+            moves: moves.clone(),
+            messages: HashMap::new(),
+            views: Vec::new(),
+            inputs: Vec::new(),
+        });
+    }
+
+    // Whole state of the match, with every creature known
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    fn batteries(&self) -> HashMap<i32, i32> {
+        self.state
+            .my_drones
+            .values()
+            .chain(self.state.their_drones.values())
+            .map(|drone| (drone.id, drone.battery))
+            .collect()
+    }
+
+    // Every turn of the match played so far, starting with the initial state
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+}
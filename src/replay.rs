@@ -0,0 +1,444 @@
+use std::{collections::HashSet, fmt::Write, fs};
+
+use crate::{
+    ascii,
+    eval::Params,
+    features,
+    protocol::Move,
+    referee::{Frame, Referee},
+    state::{
+        Color, CreatureKind, Drone, League, Outcome, LIGHT_BASE_RADIUS, LIGHT_POWER_RADIUS,
+        MAP_SIZE, MONSTER_ATTACK_RADIUS, SURFACE_Y,
+    },
+};
+
+const FRAME_SIZE: i32 = 640;
+const MY_COLOR: &str = "#ff9f1c";
+const FOE_COLOR: &str = "#c77dff";
+
+struct Options {
+    seed: u64,
+    output: String,
+    format: String,
+    player: usize,
+    turn: Option<i32>,
+    league: League,
+}
+
+fn parse_options(args: Vec<String>) -> Options {
+    let mut options = Options {
+        seed: 0,
+        output: "replay.html".to_string(),
+        format: "html".to_string(),
+        player: 0,
+        turn: None,
+        league: League::load(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--seed" => options.seed = value.parse().unwrap(),
+            "--output" => options.output = value,
+            "--format" => options.format = value,
+            "--player" => options.player = value.parse().unwrap(),
+            "--turn" => options.turn = Some(value.parse().unwrap()),
+            "--league" => options.league = League::parse(&value).unwrap(),
+            _ => panic!(
+                "unknown option {}, available options: --seed --output --format --player --turn --league",
+                arg
+            ),
+        }
+    }
+
+    options
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn fill_color(color: Option<Color>) -> &'static str {
+    match color {
+        Some(Color::Pink) => "#ff70a6",
+        Some(Color::Yellow) => "#ffd670",
+        Some(Color::Green) => "#70e000",
+        Some(Color::Blue) => "#4cc9f0",
+        None => "#9d0208",
+    }
+}
+
+// Bigger fish for the deeper, more valuable types
+fn fish_radius(kind: CreatureKind) -> i32 {
+    match kind {
+        CreatureKind::Monster => 180,
+        CreatureKind::Type0 => 100,
+        CreatureKind::Type1 => 140,
+        CreatureKind::Type2 => 180,
+    }
+}
+
+fn render_ocean(svg: &mut String) {
+    writeln!(
+        svg,
+        r##"<rect width="{0}" height="{0}" fill="#03045e"/>"##,
+        MAP_SIZE
+    )
+    .unwrap();
+    for (kind, fill) in CreatureKind::FISH
+        .iter()
+        .zip(["#023e8a", "#012a6b", "#011c4d"])
+    {
+        let (min_y, max_y) = kind.habitat();
+        writeln!(
+            svg,
+            r#"<rect y="{}" width="{}" height="{}" fill="{}"/>"#,
+            min_y,
+            MAP_SIZE,
+            max_y - min_y,
+            fill
+        )
+        .unwrap();
+    }
+    writeln!(
+        svg,
+        r##"<line x1="0" y1="{0}" x2="{1}" y2="{0}" stroke="#caf0f8" stroke-width="20" stroke-dasharray="100 100"/>"##,
+        SURFACE_Y, MAP_SIZE
+    )
+    .unwrap();
+}
+
+fn render_drone(svg: &mut String, frame: &Frame, drone: &Drone) {
+    let color = if drone.is_mine { MY_COLOR } else { FOE_COLOR };
+    let radius = if frame.lights.contains(&drone.id) {
+        LIGHT_POWER_RADIUS
+    } else {
+        LIGHT_BASE_RADIUS
+    };
+
+    writeln!(
+        svg,
+        r#"<circle cx="{}" cy="{}" r="{}" fill="{}" fill-opacity="0.12" stroke="{}" stroke-width="15"/>"#,
+        drone.x, drone.y, radius, color, color
+    )
+    .unwrap();
+    let fill = if drone.emergency == 1 {
+        "#6c757d"
+    } else {
+        color
+    };
+    writeln!(
+        svg,
+        r#"<rect x="{}" y="{}" width="300" height="300" fill="{}"/>"#,
+        drone.x - 150,
+        drone.y - 150,
+        fill
+    )
+    .unwrap();
+
+    let message = frame
+        .messages
+        .get(&drone.id)
+        .map_or(String::new(), |message| escape(message));
+    writeln!(
+        svg,
+        r#"<text x="{}" y="{}" fill="{}" font-size="220">{} ({}) {}</text>"#,
+        drone.x + 200,
+        drone.y - 200,
+        color,
+        drone.id,
+        drone.battery,
+        message
+    )
+    .unwrap();
+}
+
+fn render_frame(frame: &Frame) -> String {
+    let state = &frame.state;
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg viewBox="0 0 {0} {0}" width="{1}" height="{1}">"#,
+        MAP_SIZE, FRAME_SIZE
+    )
+    .unwrap();
+    render_ocean(&mut svg);
+
+    let mut creatures = state
+        .creatures
+        .values()
+        .filter(|creature| !state.departed_creatures.contains(&creature.id))
+        .filter(|creature| creature.x.is_some() && creature.y.is_some())
+        .collect::<Vec<_>>();
+    creatures.sort_by_key(|creature| creature.id);
+
+    let mut drones = state
+        .my_drones
+        .values()
+        .chain(state.their_drones.values())
+        .collect::<Vec<_>>();
+    drones.sort_by_key(|drone| drone.id);
+
+    // Creatures out of a drone's light are only known to it through the radar
+    for drone in drones.iter() {
+        let color = if drone.is_mine { MY_COLOR } else { FOE_COLOR };
+        for creature in creatures.iter() {
+            let is_lit = if frame.lights.contains(&drone.id) {
+                drone.is_near_creature_with_power(creature)
+            } else {
+                drone.is_near_creature(creature)
+            };
+            if is_lit {
+                continue;
+            }
+            writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-opacity="0.15" stroke-width="10"/>"#,
+                drone.x,
+                drone.y,
+                creature.x.unwrap(),
+                creature.y.unwrap(),
+                color
+            )
+            .unwrap();
+        }
+    }
+
+    for creature in creatures.iter() {
+        let (x, y) = (creature.x.unwrap(), creature.y.unwrap());
+        if creature.kind == CreatureKind::Monster {
+            writeln!(
+                svg,
+                r##"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="#d00000" stroke-width="15" stroke-dasharray="60 60"/>"##,
+                x, y, MONSTER_ATTACK_RADIUS
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"><title>{} {:?}</title></circle>"#,
+            x,
+            y,
+            fish_radius(creature.kind),
+            fill_color(creature.color),
+            creature.id,
+            creature.kind
+        )
+        .unwrap();
+    }
+
+    for drone in drones {
+        render_drone(&mut svg, frame, drone);
+    }
+
+    svg += "</svg>\n";
+    svg
+}
+
+// Scores and scans next to the frame
+fn render_sidebar(frame: &Frame) -> String {
+    let state = &frame.state;
+    let mut sidebar = format!(
+        "turn {}\nscores {} - {}\n",
+        state.turn, state.my_score, state.foe_score
+    );
+    if state.is_terminal() {
+        let (my_final_score, foe_final_score) = state.final_scores();
+        writeln!(
+            sidebar,
+            "final scores {} - {}",
+            my_final_score, foe_final_score
+        )
+        .unwrap();
+    }
+
+    for (is_mine, name) in [(true, "player 0"), (false, "player 1")] {
+        let mut saved = state
+            .saved_creatures_for(is_mine)
+            .into_iter()
+            .collect::<Vec<_>>();
+        saved.sort();
+        writeln!(sidebar, "\n{}\nsaved {:?}", name, saved).unwrap();
+
+        let mut drones = state.drones_for(is_mine).values().collect::<Vec<_>>();
+        drones.sort_by_key(|drone| drone.id);
+        for drone in drones {
+            let mut unsaved = state
+                .unsaved_creatures_for_drone(drone.id, &HashSet::new())
+                .into_iter()
+                .collect::<Vec<_>>();
+            unsaved.sort();
+            writeln!(sidebar, "drone {} carries {:?}", drone.id, unsaved).unwrap();
+        }
+    }
+
+    sidebar
+}
+
+// Self-contained page with one SVG per turn and a slider to go through them
+pub fn render(frames: &[Frame], title: &str) -> String {
+    let mut html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+<style>
+body {{ background: #111; color: #eee; font-family: monospace; }}
+.frame {{ display: none; gap: 16px; }}
+.frame.current {{ display: flex; }}
+input {{ width: {}px; }}
+</style>
+</head>
+<body>
+<h3>{}</h3>
+<input type="range" id="turn" min="0" max="{}" value="0">
+"#,
+        escape(title),
+        FRAME_SIZE,
+        escape(title),
+        frames.len() - 1
+    );
+
+    for (i, frame) in frames.iter().enumerate() {
+        writeln!(
+            html,
+            "<div class=\"frame{}\">\n{}<pre>{}</pre>\n</div>",
+            if i == 0 { " current" } else { "" },
+            render_frame(frame),
+            escape(&render_sidebar(frame))
+        )
+        .unwrap();
+    }
+
+    html += r#"<script>
+const slider = document.getElementById("turn");
+const frames = document.querySelectorAll(".frame");
+function show(i) {
+  frames.forEach((frame, j) => frame.classList.toggle("current", i === j));
+}
+slider.addEventListener("input", () => show(Number(slider.value)));
+document.addEventListener("keydown", (event) => {
+  const step = { ArrowLeft: -1, ArrowRight: 1 }[event.key];
+  if (step && document.activeElement !== slider) {
+    slider.value = Number(slider.value) + step;
+    show(Number(slider.value));
+  }
+});
+</script>
+</body>
+</html>
+"#;
+    html
+}
+
+// Match as seen by the player: its input stream, with after each turn's input
+// the action of every drone, its own drones then the opponent's, each in id order
+pub fn match_log<'a>(
+    referee: &Referee,
+    frames: impl Iterator<Item = &'a Frame>,
+    player: usize,
+) -> String {
+    let mut log = referee.creatures_input();
+
+    for frame in frames {
+        let (Some(view), Some(input)) = (frame.views.get(player), frame.inputs.get(player)) else {
+            continue;
+        };
+        log += input;
+
+        for drones in [&view.my_drones, &view.their_drones] {
+            let mut drone_ids = drones.keys().copied().collect::<Vec<_>>();
+            drone_ids.sort();
+            for drone_id in drone_ids {
+                let action = frame
+                    .moves
+                    .get(&drone_id)
+                    .map_or("WAIT 0".to_string(), Move::to_action);
+                writeln!(log, "{}", action).unwrap();
+            }
+        }
+    }
+    log
+}
+
+// Features of the player's view every turn as CSV, with their names as the header
+pub fn features_csv<'a>(frames: impl Iterator<Item = &'a Frame>, player: usize) -> String {
+    let mut csv = features::feature_names().join(",") + "\n";
+    for view in frames.filter_map(|frame| frame.views.get(player)) {
+        let row = view
+            .features()
+            .iter()
+            .map(|feature| feature.to_string())
+            .collect::<Vec<_>>();
+        writeln!(csv, "{}", row.join(",")).unwrap();
+    }
+    csv
+}
+
+// Play a match between the loaded parameters and themselves, then write its replay:
+// an HTML page, or on stdout `--player`'s view every turn with `--format ascii`,
+// its input stream with `--format inputs`, which the bot can be run on again,
+// its input stream with every drone's action with `--format log`,
+// or its features every turn as CSV with `--format features`.
+// `--turn` stops the stdout formats after the given turn.
+pub fn run(args: Vec<String>) {
+    let options = parse_options(args);
+    let params = Params::load();
+
+    let mut referee = Referee::new(options.seed, options.league);
+    let outcome = referee.play([params, params]);
+    let result = match outcome {
+        Outcome::Win => "player 0 wins",
+        Outcome::Loss => "player 1 wins",
+        Outcome::Draw => "draw",
+    };
+
+    let title = format!("seed {}: {}", options.seed, result);
+    let last_frame = options
+        .turn
+        .map_or(referee.frames().len(), |turn| turn as usize + 1);
+    let frames = referee.frames().iter().take(last_frame);
+
+    match options.format.as_str() {
+        "html" => {
+            fs::write(&options.output, render(referee.frames(), &title))
+                .expect("cannot write the output file");
+            eprintln!("{}, replay written to {}", title, options.output);
+        }
+        "ascii" => {
+            for frame in frames {
+                if let Some(view) = frame.views.get(options.player) {
+                    println!("{}", ascii::render(view));
+                }
+            }
+            eprintln!("{}", title);
+        }
+        "inputs" => {
+            print!("{}", referee.creatures_input());
+            for frame in frames {
+                if let Some(input) = frame.inputs.get(options.player) {
+                    print!("{}", input);
+                }
+            }
+            eprintln!("{}", title);
+        }
+        "log" => {
+            print!("{}", match_log(&referee, frames, options.player));
+            eprintln!("{}", title);
+        }
+        "features" => {
+            print!("{}", features_csv(frames, options.player));
+            eprintln!("{}", title);
+        }
+        _ => panic!(
+            "unknown format {}, available formats: html, ascii, inputs, log, features",
+            options.format
+        ),
+    }
+}
@@ -0,0 +1,182 @@
+// Minimax over the moves of one drone
+
+use std::{collections::HashMap, time::Instant};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    protocol::Move,
+    state::{GameState, MAP_SIZE, MOVE_SPEED},
+    trace::{DroneTrace, TraceLevel},
+};
+
+// What a search carries down the tree: when to stop, and the nodes visited so far
+pub struct SearchContext {
+    pub deadline: Option<Instant>,
+    pub nodes: u64,
+}
+
+impl GameState {
+    // None when the deadline passed before the search was over
+    pub fn minimax(
+        &self,
+        drone_id: i32,
+        depth: i32,
+        alpha: f64,
+        beta: f64,
+        maximizing_player: bool,
+        context: &mut SearchContext,
+    ) -> Option<f64> {
+        context.nodes += 1;
+
+        if context
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return None;
+        }
+        if depth == 0 || self.is_terminal() {
+            let score = self.evaluate(None);
+            return Some(score);
+        }
+
+        if maximizing_player {
+            let mut alpha = alpha;
+            for moves in self.get_possible_moves(drone_id) {
+                let mut new_state = self.clone(); // Implement Clone for GameState or find another way to get new state
+                new_state.apply_moves(&HashMap::from([(drone_id, moves)]));
+                let score = new_state.minimax(drone_id, depth - 1, alpha, beta, false, context)?;
+                alpha = f64::max(alpha, score);
+                if beta <= alpha {
+                    break;
+                }
+            }
+            Some(alpha)
+        } else {
+            let mut beta = beta;
+            for moves in self.get_possible_moves(drone_id) {
+                let mut new_state = self.clone(); // Implement Clone for GameState or find another way to get new state
+                new_state.apply_moves(&HashMap::from([(drone_id, moves)]));
+                let score = new_state.minimax(drone_id, depth - 1, alpha, beta, true, context)?;
+                beta = f64::min(beta, score);
+                if beta <= alpha {
+                    break;
+                }
+            }
+            Some(beta)
+        }
+    }
+
+    pub fn get_possible_moves(&self, drone_id: i32) -> Vec<Move> {
+        let mut possible_moves = Vec::new();
+
+        let my_drone = self.my_drones.get(&drone_id).unwrap();
+
+        let directions = vec![
+            (
+                my_drone.x + MOVE_SPEED as i32,
+                my_drone.y + MOVE_SPEED as i32,
+            ),
+            (
+                my_drone.x - MOVE_SPEED as i32,
+                my_drone.y - MOVE_SPEED as i32,
+            ),
+            (
+                my_drone.x + MOVE_SPEED as i32,
+                my_drone.y - MOVE_SPEED as i32,
+            ),
+            (
+                my_drone.x - MOVE_SPEED as i32,
+                my_drone.y + MOVE_SPEED as i32,
+            ),
+        ];
+        let light_values = vec![true, false];
+        for direction in directions {
+            for light in light_values.clone() {
+                let m = Move {
+                    should_move: true,
+                    x: Some(i32::max(0, i32::min(MAP_SIZE, direction.0))),
+                    y: Some(i32::max(0, i32::min(MAP_SIZE, direction.1))),
+                    light,
+                };
+
+                possible_moves.push(m);
+            }
+        }
+
+        for light in light_values {
+            let m = Move {
+                should_move: false,
+                x: None,
+                y: None,
+                light,
+            };
+
+            possible_moves.push(m);
+        }
+
+        possible_moves
+    }
+
+    // Iterative deepening up to the search depth, each depth trying the best move of the
+    // previous one first. The moves that cannot beat the best one so far are cut short,
+    // their traced score being only an upper bound. When the deadline passes, the best move
+    // of the deepest search finished is played.
+    pub fn find_best_move(
+        &self,
+        drone_id: i32,
+        deadline: Option<Instant>,
+        trace: &mut DroneTrace,
+    ) -> Option<Move> {
+        // Shuffle the possible moves to avoid always picking the same one when evaluation is equal
+        let mut moves = self.get_possible_moves(drone_id);
+        match self.search_seed {
+            Some(seed) => {
+                let seed = seed ^ ((self.turn as u64) << 32) ^ drone_id as u64;
+                moves.shuffle(&mut StdRng::seed_from_u64(seed));
+            }
+            None => moves.shuffle(&mut rand::thread_rng()),
+        }
+
+        let max_depth = self.params.search_depth.round() as i32;
+        let mut best_move = None;
+        let mut context = SearchContext {
+            deadline: None,
+            nodes: 0,
+        };
+        for depth in 0..=max_depth {
+            // The shallowest search always finishes, so that there is a move to play
+            context.deadline = if depth == 0 { None } else { deadline };
+            let mut best: Option<(usize, f64)> = None;
+            let mut candidates = Vec::new();
+
+            for (i, m) in moves.iter().enumerate() {
+                let mut new_state = self.clone();
+                new_state.apply_moves(&HashMap::from([(drone_id, m.clone())]));
+
+                let alpha = best.map_or(i32::MIN as f64, |(_, best_score)| best_score);
+                let score =
+                    new_state.minimax(drone_id, depth, alpha, i32::MAX as f64, true, &mut context);
+                trace.nodes = context.nodes;
+                let Some(score) = score else {
+                    return best_move;
+                };
+                if self.trace_level >= TraceLevel::Search {
+                    candidates.push((m.clone(), score));
+                }
+                if best.is_none_or(|(_, best_score)| score > best_score) {
+                    best = Some((i, score));
+                }
+            }
+
+            let (i, _) = best?;
+            let m = moves.remove(i);
+            moves.insert(0, m);
+            best_move = Some(moves[0].clone());
+            trace.depth = Some(depth);
+            trace.candidates = candidates;
+        }
+
+        best_move
+    }
+}
@@ -0,0 +1,429 @@
+// Forward model playing one turn of the game on a state
+
+use std::{
+    cmp,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    protocol::Move,
+    state::{
+        Color, CreatureKind, Drone, GameState, EMERGENCY_RISE_SPEED, FISH_FLEE_RADIUS,
+        FISH_FLEE_SPEED, FISH_SPEED, LIGHT_BASE_RADIUS, LIGHT_BATTERY_COST, LIGHT_POWER_RADIUS,
+        MAP_SIZE, MAX_BATTERY, MIN_BATTERY, MONSTER_ATTACK_RADIUS, MONSTER_CHASE_SPEED,
+        MONSTER_SPEED, MOVE_SPEED, SINK_SPEED, SURFACE_Y,
+    },
+};
+
+pub fn normalize_vector(x: f64, y: f64) -> (f64, f64) {
+    let norm = x.powf(2.0) + y.powf(2.0);
+    let norm = norm.sqrt();
+    let x = x / norm;
+    let y = y / norm;
+    (x, y)
+}
+
+// Closest distance between two points both moving in a straight line during the same turn
+pub fn closest_approach(
+    from: (f64, f64),
+    to: (f64, f64),
+    other_from: (f64, f64),
+    other_to: (f64, f64),
+) -> f64 {
+    let (dx, dy) = (from.0 - other_from.0, from.1 - other_from.1);
+    let relative_x = (to.0 - from.0) - (other_to.0 - other_from.0);
+    let relative_y = (to.1 - from.1) - (other_to.1 - other_from.1);
+    let relative_speed = relative_x.powf(2.0) + relative_y.powf(2.0);
+
+    let t = if relative_speed == 0.0 {
+        0.0
+    } else {
+        (-(dx * relative_x + dy * relative_y) / relative_speed).clamp(0.0, 1.0)
+    };
+    ((dx + relative_x * t).powf(2.0) + (dy + relative_y * t).powf(2.0)).sqrt()
+}
+
+// Where the drone ends up when moving toward the target for one turn
+pub fn step_toward(from: (f64, f64), target: (f64, f64)) -> (f64, f64) {
+    let distance = ((target.0 - from.0).powf(2.0) + (target.1 - from.1).powf(2.0)).sqrt();
+    if distance <= MOVE_SPEED {
+        return target;
+    }
+    let (normalized_x, normalized_y) = normalize_vector(target.0 - from.0, target.1 - from.1);
+    (
+        from.0 + normalized_x * MOVE_SPEED,
+        from.1 + normalized_y * MOVE_SPEED,
+    )
+}
+
+// Monster (x, y, vx, vy) after one turn: it keeps its speed unless the drone is
+// within its light radius, in which case it charges toward it
+pub fn predict_monster(
+    monster: (f64, f64, f64, f64),
+    drone: (f64, f64),
+    light_radius: f64,
+) -> (f64, f64, f64, f64) {
+    let (x, y, vx, vy) = monster;
+    let (x, y) = (x + vx, f64::max(2500.0, f64::min(MAP_SIZE as f64, y + vy)));
+    let distance = ((drone.0 - x).powf(2.0) + (drone.1 - y).powf(2.0)).sqrt();
+
+    if distance <= light_radius {
+        let (normalized_x, normalized_y) = normalize_vector(drone.0 - x, drone.1 - y);
+        (
+            x,
+            y,
+            normalized_x * MONSTER_CHASE_SPEED,
+            normalized_y * MONSTER_CHASE_SPEED,
+        )
+    } else {
+        (x, y, vx, vy)
+    }
+}
+
+impl Drone {
+    // Move toward the target, or sink without one, staying on the map
+    pub fn step(&mut self, m: &Move) {
+        if m.should_move {
+            let target_x = m.x.unwrap() as f64;
+            let target_y = m.y.unwrap() as f64;
+            if self.distance_from(target_x, target_y) <= MOVE_SPEED {
+                self.x = target_x as i32;
+                self.y = target_y as i32;
+            } else {
+                let (normalized_x, normalized_y) =
+                    normalize_vector(target_x - self.x as f64, target_y - self.y as f64);
+                self.x += (normalized_x * MOVE_SPEED) as i32;
+                self.y += (normalized_y * MOVE_SPEED) as i32;
+            }
+        } else {
+            self.y += SINK_SPEED as i32;
+        }
+        self.x = i32::max(0, i32::min(MAP_SIZE, self.x));
+        self.y = i32::max(0, i32::min(MAP_SIZE, self.y));
+    }
+}
+
+impl GameState {
+    // Drones without a move in `moves` stay where they are
+    pub fn apply_moves(&mut self, moves: &HashMap<i32, Move>) {
+        let monster_starts = self
+            .creatures
+            .values()
+            .filter(|creature| !creature.kind.is_fish() && self.league.has_monsters())
+            .filter_map(|creature| Some((creature.id, (creature.x? as f64, creature.y? as f64))))
+            .collect::<Vec<_>>();
+
+        for creature in self.creatures.values_mut() {
+            if self.departed_creatures.contains(&creature.id) {
+                continue;
+            }
+
+            creature.x = creature.x.map(|x| x + creature.vx.unwrap());
+            creature.y = creature.y.map(|y| y + creature.vy.unwrap());
+
+            // Fish fleeing through the side of the map never come back
+            let has_left_map = creature.x.is_some_and(|x| !(0..MAP_SIZE).contains(&x));
+            if creature.kind.is_fish() && has_left_map {
+                self.departed_creatures.insert(creature.id);
+            }
+        }
+
+        let mut drone_ids = moves.keys().copied().collect::<Vec<_>>();
+        drone_ids.sort();
+        let mut drone_starts = Vec::new();
+        let mut lit_drone_ids = HashSet::new();
+
+        for drone_id in drone_ids {
+            let m = moves.get(&drone_id).unwrap();
+
+            let drone = self.drone_mut(drone_id);
+            drone_starts.push((drone_id, (drone.x as f64, drone.y as f64)));
+
+            // Drones hit by a monster float back to the surface and do nothing else
+            if drone.emergency == 1 {
+                drone.y = cmp::max(0, drone.y - EMERGENCY_RISE_SPEED as i32);
+                drone.battery = cmp::min(MAX_BATTERY, drone.battery + 1);
+                if drone.y as f64 <= SURFACE_Y {
+                    drone.emergency = 0;
+                }
+                continue;
+            }
+
+            drone.step(m);
+
+            // The light only turns on when there is enough battery left
+            let light = m.light && drone.battery >= LIGHT_BATTERY_COST;
+            drone.battery = if light {
+                cmp::max(MIN_BATTERY, drone.battery - LIGHT_BATTERY_COST)
+            } else {
+                cmp::min(MAX_BATTERY, drone.battery + 1)
+            };
+            if light {
+                lit_drone_ids.insert(drone_id);
+            }
+
+            // Fish out of sight are only scanned when the drone reaches them wherever
+            // their extrapolated position may have drifted
+            let drone = self.drone(drone_id);
+            let saved = self.saved_creatures_for(drone.is_mine);
+            let radius = if light {
+                LIGHT_POWER_RADIUS
+            } else {
+                LIGHT_BASE_RADIUS
+            };
+            let scanned_creature_ids = self
+                .creatures
+                .values()
+                .filter(|creature| {
+                    creature.kind.is_fish() && !self.departed_creatures.contains(&creature.id)
+                })
+                .filter(|creature| match (creature.x, creature.y) {
+                    (Some(x), Some(y)) => {
+                        drone.distance_from(x as f64, y as f64) + creature.drift(self.turn)
+                            <= radius
+                    }
+                    _ => false,
+                })
+                .filter(|creature| !saved.contains(&creature.id))
+                .map(|creature| creature.id)
+                .collect::<Vec<_>>();
+
+            for creature_id in scanned_creature_ids {
+                self.drone_scans.insert((drone_id, creature_id));
+            }
+        }
+
+        if self.league.has_monsters() {
+            self.attack_drones(&monster_starts, &drone_starts);
+            self.update_monster_speeds(&lit_drone_ids);
+        }
+        self.update_creature_speeds();
+        self.save_surfaced_scans();
+        self.turn += 1;
+    }
+
+    // Drones passing within reach of a monster during the turn lose their unsaved scans
+    // and go into emergency, the reach of a monster out of sight growing with its drift
+    fn attack_drones(
+        &mut self,
+        monster_starts: &[(i32, (f64, f64))],
+        drone_starts: &[(i32, (f64, f64))],
+    ) {
+        for (drone_id, drone_start) in drone_starts {
+            let drone = self.drone(*drone_id);
+            if drone.emergency == 1 {
+                continue;
+            }
+
+            let drone_end = (drone.x as f64, drone.y as f64);
+            let is_hit = monster_starts.iter().any(|(monster_id, monster_start)| {
+                let monster = self.creatures.get(monster_id).unwrap();
+                let monster_end = (monster.x.unwrap() as f64, monster.y.unwrap() as f64);
+                closest_approach(*drone_start, drone_end, *monster_start, monster_end)
+                    <= MONSTER_ATTACK_RADIUS + monster.drift(self.turn)
+            });
+
+            if is_hit {
+                self.drone_mut(*drone_id).emergency = 1;
+                self.drone_scans
+                    .retain(|(scan_drone_id, _)| scan_drone_id != drone_id);
+            }
+        }
+    }
+
+    // Monsters charge at the closest drone whose light reaches them, otherwise they slow
+    // down to their normal speed and bounce on the borders of the map and of their habitat
+    pub fn update_monster_speeds(&mut self, lit_drone_ids: &HashSet<i32>) {
+        let drones = self
+            .my_drones
+            .values()
+            .chain(self.their_drones.values())
+            .filter(|drone| drone.emergency == 0)
+            .map(|drone| {
+                let radius = if lit_drone_ids.contains(&drone.id) {
+                    LIGHT_POWER_RADIUS
+                } else {
+                    LIGHT_BASE_RADIUS
+                };
+                (drone.x as f64, drone.y as f64, radius)
+            })
+            .collect::<Vec<_>>();
+
+        for creature in self.creatures.values_mut() {
+            if creature.kind.is_fish() || creature.x.is_none() {
+                continue;
+            }
+
+            let x = creature.x.unwrap() as f64;
+            let y = creature.y.unwrap() as f64;
+            let target = drones
+                .iter()
+                .map(|(drone_x, drone_y, radius)| {
+                    let distance = ((x - drone_x).powf(2.0) + (y - drone_y).powf(2.0)).sqrt();
+                    (drone_x, drone_y, distance, radius)
+                })
+                .filter(|(_, _, distance, radius)| distance <= radius)
+                .min_by(|a, b| a.2.total_cmp(&b.2));
+
+            let (mut vx, mut vy) = match target {
+                Some((drone_x, drone_y, _, _)) => {
+                    let (normalized_x, normalized_y) = normalize_vector(drone_x - x, drone_y - y);
+                    (
+                        normalized_x * MONSTER_CHASE_SPEED,
+                        normalized_y * MONSTER_CHASE_SPEED,
+                    )
+                }
+                None => {
+                    let vx = creature.vx.unwrap_or(0) as f64;
+                    let vy = creature.vy.unwrap_or(0) as f64;
+                    if (vx.powf(2.0) + vy.powf(2.0)).sqrt() > MONSTER_SPEED {
+                        let (normalized_x, normalized_y) = normalize_vector(vx, vy);
+                        (normalized_x * MONSTER_SPEED, normalized_y * MONSTER_SPEED)
+                    } else {
+                        (vx, vy)
+                    }
+                }
+            };
+
+            let (min_y, max_y) = creature.habitat();
+            if y + vy < min_y as f64 || y + vy > max_y as f64 {
+                vy = -vy;
+            }
+            if x + vx < 0.0 || x + vx > (MAP_SIZE - 1) as f64 {
+                vx = -vx;
+            }
+
+            creature.vx = Some(vx.round() as i32);
+            creature.vy = Some(vy.round() as i32);
+        }
+    }
+
+    // Fish flee from the drones close to them, otherwise they swim at normal speed
+    // and bounce on the borders of the map and of their habitat
+    pub fn update_creature_speeds(&mut self) {
+        let drone_positions = self
+            .my_drones
+            .values()
+            .chain(self.their_drones.values())
+            .map(|drone| (drone.x as f64, drone.y as f64))
+            .collect::<Vec<_>>();
+
+        for creature in self.creatures.values_mut() {
+            if !creature.kind.is_fish()
+                || creature.x.is_none()
+                || self.departed_creatures.contains(&creature.id)
+            {
+                continue;
+            }
+
+            let x = creature.x.unwrap() as f64;
+            let y = creature.y.unwrap() as f64;
+            let scaring_drones = drone_positions
+                .iter()
+                .filter(|(drone_x, drone_y)| {
+                    ((x - drone_x).powf(2.0) + (y - drone_y).powf(2.0)).sqrt() <= FISH_FLEE_RADIUS
+                })
+                .collect::<Vec<_>>();
+
+            let is_fleeing = !scaring_drones.is_empty();
+            let (mut vx, mut vy) = if is_fleeing {
+                let count = scaring_drones.len() as f64;
+                let center_x = scaring_drones
+                    .iter()
+                    .map(|(drone_x, _)| drone_x)
+                    .sum::<f64>()
+                    / count;
+                let center_y = scaring_drones
+                    .iter()
+                    .map(|(_, drone_y)| drone_y)
+                    .sum::<f64>()
+                    / count;
+                let (normalized_x, normalized_y) = normalize_vector(x - center_x, y - center_y);
+                (
+                    normalized_x * FISH_FLEE_SPEED,
+                    normalized_y * FISH_FLEE_SPEED,
+                )
+            } else {
+                let vx = creature.vx.unwrap() as f64;
+                let vy = creature.vy.unwrap() as f64;
+                if (vx.powf(2.0) + vy.powf(2.0)).sqrt() > FISH_SPEED {
+                    let (normalized_x, normalized_y) = normalize_vector(vx, vy);
+                    (normalized_x * FISH_SPEED, normalized_y * FISH_SPEED)
+                } else {
+                    (vx, vy)
+                }
+            };
+
+            let (min_y, max_y) = creature.habitat();
+            if y + vy < min_y as f64 || y + vy > max_y as f64 {
+                vy = -vy;
+            }
+            if !is_fleeing && (x + vx < 0.0 || x + vx > (MAP_SIZE - 1) as f64) {
+                vx = -vx;
+            }
+
+            creature.vx = Some(vx.round() as i32);
+            creature.vy = Some(vy.round() as i32);
+        }
+    }
+
+    // Drones at the surface save their scans, both players at the same time
+    pub fn save_surfaced_scans(&mut self) {
+        // Nothing changes unless a drone at the surface carries scans, which is most turns
+        let has_scans_to_save = self.drone_scans.iter().any(|(drone_id, _)| {
+            let drone = self.drone(*drone_id);
+            drone.y as f64 <= SURFACE_Y
+        });
+        if !has_scans_to_save {
+            return;
+        }
+
+        let my_saved = self.saved_creatures_for(true);
+        let foe_saved = self.saved_creatures_for(false);
+        let mut my_newly_saved = HashSet::new();
+        let mut foe_newly_saved = HashSet::new();
+        let mut surfaced_drone_ids = Vec::new();
+
+        for drone in self.my_drones.values().chain(self.their_drones.values()) {
+            if drone.y as f64 > SURFACE_Y {
+                continue;
+            }
+            if drone.is_mine {
+                my_newly_saved.extend(self.unsaved_creatures_for_drone(drone.id, &my_saved));
+            } else {
+                foe_newly_saved.extend(self.unsaved_creatures_for_drone(drone.id, &foe_saved));
+            }
+            surfaced_drone_ids.push((drone.id, drone.is_mine));
+        }
+
+        self.my_score += self.points_for_save(&my_saved, &my_newly_saved, &foe_saved);
+        self.foe_score += self.points_for_save(&foe_saved, &foe_newly_saved, &my_saved);
+        self.my_scan_count += my_newly_saved.len() as i32;
+        self.foe_scan_count += foe_newly_saved.len() as i32;
+
+        let creature_ids = self.creatures.keys().copied().collect::<Vec<_>>();
+        for (drone_id, is_mine) in surfaced_drone_ids {
+            for creature_id in creature_ids.iter() {
+                let key = (drone_id, *creature_id);
+                if self.drone_scans.remove(&key) {
+                    self.scans.insert(key);
+                }
+            }
+
+            if is_mine {
+                if Color::ALL
+                    .iter()
+                    .any(|color| self.has_scanned_all_creatures_of_color_for(*color, drone_id))
+                {
+                    self.was_all_colors_achieved = true;
+                }
+                if CreatureKind::FISH
+                    .iter()
+                    .any(|kind| self.has_scanned_one_of_each_for(*kind, drone_id))
+                {
+                    self.was_1_of_each_achieved = true;
+                }
+            }
+        }
+    }
+}
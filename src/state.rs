@@ -0,0 +1,548 @@
+// Game rules and the state of the game as known by the bot
+
+use std::{
+    cmp,
+    collections::{HashMap, HashSet},
+    env,
+};
+
+use crate::{
+    eval::Params,
+    strategy::Assignment,
+    trace::TraceLevel,
+    tracker::{Radar, Visibility, CONFIDENCE_DECAY, MAX_POSITION_DRIFT, MIN_CONFIDENCE},
+};
+
+pub const MAP_SIZE: i32 = 10000;
+pub const MOVE_SPEED: f64 = 600.0;
+pub const SINK_SPEED: f64 = 300.0;
+pub const LIGHT_BASE_RADIUS: f64 = 800.0;
+pub const LIGHT_POWER_RADIUS: f64 = 2000.0;
+pub const MIN_BATTERY: i32 = 0;
+pub const MAX_BATTERY: i32 = 30;
+pub const LIGHT_BATTERY_COST: i32 = 5;
+pub const FISH_SPEED: f64 = 200.0;
+pub const FISH_FLEE_SPEED: f64 = 400.0;
+pub const FISH_FLEE_RADIUS: f64 = 1400.0;
+pub const SURFACE_Y: f64 = 500.0;
+pub const COLOR_COMBO_POINTS: i32 = 3;
+pub const TYPE_COMBO_POINTS: i32 = 4;
+pub const MAX_TURNS: i32 = 200;
+pub const MONSTER_SPEED: f64 = 270.0;
+pub const MONSTER_CHASE_SPEED: f64 = 540.0;
+pub const MONSTER_ATTACK_RADIUS: f64 = 500.0;
+pub const EMERGENCY_RISE_SPEED: f64 = 300.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CreatureKind {
+    Monster,
+    Type0,
+    Type1,
+    Type2,
+}
+impl CreatureKind {
+    pub const FISH: [CreatureKind; 3] = [
+        CreatureKind::Type0,
+        CreatureKind::Type1,
+        CreatureKind::Type2,
+    ];
+
+    pub fn parse(value: i32) -> Result<CreatureKind, String> {
+        match value {
+            -1 => Ok(CreatureKind::Monster),
+            0 => Ok(CreatureKind::Type0),
+            1 => Ok(CreatureKind::Type1),
+            2 => Ok(CreatureKind::Type2),
+            _ => Err(format!("invalid creature type {}", value)),
+        }
+    }
+
+    // Value used by the game protocol
+    pub fn id(self) -> i32 {
+        match self {
+            CreatureKind::Monster => -1,
+            CreatureKind::Type0 => 0,
+            CreatureKind::Type1 => 1,
+            CreatureKind::Type2 => 2,
+        }
+    }
+
+    pub fn is_fish(self) -> bool {
+        self != CreatureKind::Monster
+    }
+
+    pub fn score(self) -> i32 {
+        match self {
+            CreatureKind::Monster => 0,
+            CreatureKind::Type0 => 1,
+            CreatureKind::Type1 => 2,
+            CreatureKind::Type2 => 3,
+        }
+    }
+
+    // Range of depths the creature never leaves
+    pub fn habitat(self) -> (i32, i32) {
+        match self {
+            CreatureKind::Monster => (2500, 10000),
+            CreatureKind::Type0 => (2500, 5000),
+            CreatureKind::Type1 => (5000, 7500),
+            CreatureKind::Type2 => (7500, 10000),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    Pink,
+    Yellow,
+    Green,
+    Blue,
+}
+impl Color {
+    pub const ALL: [Color; 4] = [Color::Pink, Color::Yellow, Color::Green, Color::Blue];
+
+    // Fish always have a color, monsters never do
+    pub fn parse(value: i32, kind: CreatureKind) -> Result<Option<Color>, String> {
+        match (value, kind) {
+            (-1, CreatureKind::Monster) => Ok(None),
+            (_, CreatureKind::Monster) => Err(format!("invalid monster color {}", value)),
+            (0, _) => Ok(Some(Color::Pink)),
+            (1, _) => Ok(Some(Color::Yellow)),
+            (2, _) => Ok(Some(Color::Green)),
+            (3, _) => Ok(Some(Color::Blue)),
+            _ => Err(format!("invalid fish color {}", value)),
+        }
+    }
+
+    // Value used by the game protocol
+    pub fn id(self) -> i32 {
+        match self {
+            Color::Pink => 0,
+            Color::Yellow => 1,
+            Color::Green => 2,
+            Color::Blue => 3,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Creature {
+    pub id: i32,
+    pub color: Option<Color>,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub vx: Option<i32>,
+    pub vy: Option<i32>,
+    pub kind: CreatureKind,
+    pub last_seen_turn: Option<i32>,
+}
+impl Creature {
+    // How much the known position can be trusted, from 1 when seen this turn
+    // down to 0 when never seen, since it drifts further every turn out of sight
+    pub fn confidence(&self, turn: i32) -> f64 {
+        match self.last_seen_turn {
+            Some(last_seen_turn) => CONFIDENCE_DECAY.powi(turn - last_seen_turn),
+            None => 0.0,
+        }
+    }
+
+    // Distance between the known position and where the creature may really be,
+    // 0 when it is visible
+    pub fn drift(&self, turn: i32) -> f64 {
+        match self.visibility(turn) {
+            Visibility::Visible => 0.0,
+            _ => (1.0 - self.confidence(turn)) * MAX_POSITION_DRIFT,
+        }
+    }
+
+    pub fn visibility(&self, turn: i32) -> Visibility {
+        if self.last_seen_turn == Some(turn) {
+            Visibility::Visible
+        } else if self.x.is_some() && self.confidence(turn) >= MIN_CONFIDENCE {
+            Visibility::RecentlySeen
+        } else {
+            Visibility::Unknown
+        }
+    }
+
+    // Range of depths the creature never leaves
+    pub fn habitat(&self) -> (i32, i32) {
+        self.kind.habitat()
+    }
+
+    pub fn get_score(&self) -> i32 {
+        self.kind.score()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Drone {
+    pub id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub emergency: i32,
+    pub battery: i32,
+    pub is_mine: bool,
+}
+impl Drone {
+    pub fn distance_from(&self, x: f64, y: f64) -> f64 {
+        ((self.x as f64 - x).powf(2.0) + (self.y as f64 - y).powf(2.0)).sqrt()
+    }
+
+    pub fn is_near_creature(&self, creature: &Creature) -> bool {
+        let distance = self.distance_from(creature.x.unwrap() as f64, creature.y.unwrap() as f64);
+        distance <= LIGHT_BASE_RADIUS
+    }
+
+    pub fn is_near_creature_with_power(&self, creature: &Creature) -> bool {
+        let distance = self.distance_from(creature.x.unwrap() as f64, creature.y.unwrap() as f64);
+        distance <= LIGHT_POWER_RADIUS
+    }
+
+    // Number of turns needed to reach the surface going straight up
+    pub fn turns_to_surface(&self) -> i32 {
+        let turns = ((self.y as f64 - SURFACE_Y) / MOVE_SPEED).ceil();
+        f64::max(0.0, turns) as i32
+    }
+}
+
+// League baked in the submission with `bundle --league`, empty to tell it from the input
+pub const SUBMITTED_LEAGUE: &str = "";
+
+// Rules of the league the game is played in, set with FALL2023_LEAGUE (wood2, wood1
+// or bronze for Bronze and above, the default). The later leagues add drones,
+// monsters and the radar, and the first one has no bonus points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum League {
+    Wood2,
+    Wood1,
+    Bronze,
+}
+impl League {
+    pub fn parse(value: &str) -> Result<League, String> {
+        match value.trim() {
+            "wood2" => Ok(League::Wood2),
+            "wood1" => Ok(League::Wood1),
+            "" | "bronze" => Ok(League::Bronze),
+            _ => Err(format!("unknown league `{}`", value)),
+        }
+    }
+
+    pub fn load() -> League {
+        let value = env::var("FALL2023_LEAGUE").unwrap_or_default();
+        League::parse(&value).unwrap_or_else(|error| panic!("invalid league: {}", error))
+    }
+
+    // League of a game given its creatures: FALL2023_LEAGUE when set, then the league baked in
+    // the submission, otherwise Bronze when there are monsters and Wood 1 when there are none,
+    // Wood 2 having the same creatures as Wood 1
+    pub fn detect(creatures: &HashMap<i32, Creature>) -> League {
+        if let Ok(value) = env::var("FALL2023_LEAGUE") {
+            return League::parse(&value)
+                .unwrap_or_else(|error| panic!("invalid league: {}", error));
+        }
+        if !SUBMITTED_LEAGUE.is_empty() {
+            return League::parse(SUBMITTED_LEAGUE).unwrap();
+        }
+        if creatures.values().any(|creature| !creature.kind.is_fish()) {
+            League::Bronze
+        } else {
+            League::Wood1
+        }
+    }
+
+    pub fn drone_count(self) -> i32 {
+        match self {
+            League::Wood2 | League::Wood1 => 1,
+            League::Bronze => 2,
+        }
+    }
+
+    pub fn has_monsters(self) -> bool {
+        self == League::Bronze
+    }
+
+    // Without the radar, every creature still in the game is visible
+    pub fn has_radar(self) -> bool {
+        self == League::Bronze
+    }
+
+    // Combos and the double points for saving first
+    pub fn has_bonuses(self) -> bool {
+        self != League::Wood2
+    }
+}
+
+// Scans of a player, shared by the terms of the evaluation and the features
+#[derive(Clone, Debug, Default)]
+pub struct PlayerScans {
+    pub saved: HashSet<i32>,
+    // Unsaved scans of each drone
+    pub carried: HashMap<i32, HashSet<i32>>,
+    pub unsaved: HashSet<i32>,
+    // Fish still on the map neither saved nor scanned, in id order
+    pub unscanned: Vec<i32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameState {
+    pub was_1_of_each_achieved: bool,
+    pub was_all_colors_achieved: bool,
+    pub turn: i32,
+    pub my_score: i32,
+    pub foe_score: i32,
+    pub my_scan_count: i32,
+    pub foe_scan_count: i32,
+    pub my_drone_count: i32,
+    pub foe_drone_count: i32,
+    pub creatures: HashMap<i32, Creature>,
+    pub my_drones: HashMap<i32, Drone>,
+    pub their_drones: HashMap<i32, Drone>,
+    pub radar: Radar,
+    // Saved and carried scans as (drone id, creature id)
+    pub scans: HashSet<(i32, i32)>,
+    pub drone_scans: HashSet<(i32, i32)>,
+    pub departed_creatures: HashSet<i32>,
+    pub league: League,
+    pub assignment: Assignment,
+    // Whether my drones are going up with their scans, kept until they are saved
+    pub is_racing: bool,
+    pub params: Params,
+    pub trace_level: TraceLevel,
+    // Seed of the order the search tries the moves in, random when none
+    pub search_seed: Option<u64>,
+}
+impl GameState {
+    pub fn drone(&self, drone_id: i32) -> &Drone {
+        match self.my_drones.get(&drone_id) {
+            Some(drone) => drone,
+            None => self.their_drones.get(&drone_id).unwrap(),
+        }
+    }
+
+    pub fn drone_mut(&mut self, drone_id: i32) -> &mut Drone {
+        match self.my_drones.get_mut(&drone_id) {
+            Some(drone) => drone,
+            None => self.their_drones.get_mut(&drone_id).unwrap(),
+        }
+    }
+
+    pub fn drones_for(&self, is_mine: bool) -> &HashMap<i32, Drone> {
+        if is_mine {
+            &self.my_drones
+        } else {
+            &self.their_drones
+        }
+    }
+
+    // The game stops after the last turn, or earlier once both players
+    // have saved every fish there is left to save
+    pub fn is_terminal(&self) -> bool {
+        if self.turn >= MAX_TURNS {
+            return true;
+        }
+
+        let fish_ids = self
+            .creatures
+            .values()
+            .filter(|creature| creature.kind.is_fish())
+            .filter(|creature| !self.departed_creatures.contains(&creature.id))
+            .map(|creature| creature.id)
+            .collect::<HashSet<_>>();
+        // Each player needs a saved scan of every fish, which is rarely the case
+        if self.scans.len() < 2 * fish_ids.len() {
+            return false;
+        }
+        fish_ids.is_subset(&self.saved_creatures_for(true))
+            && fish_ids.is_subset(&self.saved_creatures_for(false))
+    }
+
+    // Scores once the scans still carried by the drones are saved, as done when the game ends
+    pub fn final_scores(&self) -> (i32, i32) {
+        let my_saved = self.saved_creatures_for(true);
+        let foe_saved = self.saved_creatures_for(false);
+        let my_unsaved = self.unsaved_creatures_for(true);
+        let foe_unsaved = self.unsaved_creatures_for(false);
+
+        (
+            self.my_score + self.points_for_save(&my_saved, &my_unsaved, &foe_saved),
+            self.foe_score + self.points_for_save(&foe_saved, &foe_unsaved, &my_saved),
+        )
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        if !self.is_terminal() {
+            return None;
+        }
+
+        let (my_final_score, foe_final_score) = self.final_scores();
+        Some(match my_final_score.cmp(&foe_final_score) {
+            cmp::Ordering::Greater => Outcome::Win,
+            cmp::Ordering::Less => Outcome::Loss,
+            cmp::Ordering::Equal => Outcome::Draw,
+        })
+    }
+
+    // Creatures already saved by the player
+    pub fn saved_creatures_for(&self, is_mine: bool) -> HashSet<i32> {
+        let drones = self.drones_for(is_mine);
+        self.scans
+            .iter()
+            .filter(|(drone_id, _)| drones.contains_key(drone_id))
+            .map(|(_, creature_id)| *creature_id)
+            .collect()
+    }
+
+    // Creatures scanned by the drone but not saved yet
+    pub fn unsaved_creatures_for_drone(&self, drone_id: i32, saved: &HashSet<i32>) -> HashSet<i32> {
+        self.drone_scans
+            .iter()
+            .filter(|(scan_drone_id, creature_id)| {
+                *scan_drone_id == drone_id && !saved.contains(creature_id)
+            })
+            .map(|(_, creature_id)| *creature_id)
+            .collect()
+    }
+
+    // Creatures scanned by any of the player's drones but not saved yet
+    pub fn unsaved_creatures_for(&self, is_mine: bool) -> HashSet<i32> {
+        self.player_scans(is_mine).unsaved
+    }
+
+    // Fish the player has neither saved nor scanned yet
+    pub fn unscanned_creatures_for(&self, is_mine: bool) -> Vec<i32> {
+        self.player_scans(is_mine).unscanned
+    }
+
+    // Saved, carried and unscanned fish of the player, worked out in one pass
+    pub fn player_scans(&self, is_mine: bool) -> PlayerScans {
+        let drones = self.drones_for(is_mine);
+        let saved = self.saved_creatures_for(is_mine);
+        let mut carried: HashMap<i32, HashSet<i32>> = drones
+            .keys()
+            .map(|drone_id| (*drone_id, HashSet::new()))
+            .collect();
+        let mut unsaved = HashSet::new();
+        for (drone_id, creature_id) in self.drone_scans.iter() {
+            if let Some(drone_carried) = carried.get_mut(drone_id) {
+                if !saved.contains(creature_id) {
+                    drone_carried.insert(*creature_id);
+                    unsaved.insert(*creature_id);
+                }
+            }
+        }
+
+        let mut unscanned = self
+            .creatures
+            .values()
+            .filter(|creature| creature.kind.is_fish())
+            .filter(|creature| !self.departed_creatures.contains(&creature.id))
+            .filter(|creature| !saved.contains(&creature.id) && !unsaved.contains(&creature.id))
+            .map(|creature| creature.id)
+            .collect::<Vec<_>>();
+        unscanned.sort();
+
+        PlayerScans {
+            saved,
+            carried,
+            unsaved,
+            unscanned,
+        }
+    }
+
+    // Fish needed for each combo with the points it is worth:
+    // every kind of fish of a color, and every color of a kind of fish
+    pub fn combos(&self) -> Vec<(Vec<i32>, i32)> {
+        let mut colors = Color::ALL.map(|_| Vec::new());
+        let mut kinds = CreatureKind::FISH.map(|_| Vec::new());
+        for creature in self.creatures.values() {
+            if let Some(color) = creature.color {
+                colors[color.id() as usize].push(creature.id);
+            }
+            if creature.kind.is_fish() {
+                kinds[creature.kind.id() as usize].push(creature.id);
+            }
+        }
+
+        let mut combos = Vec::with_capacity(colors.len() + kinds.len());
+        combos.extend(colors.map(|creature_ids| (creature_ids, COLOR_COMBO_POINTS)));
+        combos.extend(kinds.map(|creature_ids| (creature_ids, TYPE_COMBO_POINTS)));
+        combos
+    }
+
+    // Points earned when `newly_saved` is saved on top of `already_saved`.
+    // Creatures and combos that are not in `opponent_saved` yet are worth double,
+    // in the leagues with bonuses.
+    pub fn points_for_save(
+        &self,
+        already_saved: &HashSet<i32>,
+        newly_saved: &HashSet<i32>,
+        opponent_saved: &HashSet<i32>,
+    ) -> i32 {
+        let all_saved: HashSet<i32> = already_saved.union(newly_saved).copied().collect();
+        let mut points = 0;
+
+        for creature_id in newly_saved {
+            if already_saved.contains(creature_id) {
+                continue;
+            }
+            let creature = self.creatures.get(creature_id).unwrap();
+            let creature_score = creature.get_score();
+            points += if opponent_saved.contains(creature_id) || !self.league.has_bonuses() {
+                creature_score
+            } else {
+                creature_score * 2
+            };
+        }
+
+        if !self.league.has_bonuses() {
+            return points;
+        }
+
+        for (creature_ids, combo_points) in self.combos() {
+            let is_complete = |saved: &HashSet<i32>| {
+                creature_ids
+                    .iter()
+                    .all(|creature_id| saved.contains(creature_id))
+            };
+            if is_complete(&all_saved) && !is_complete(already_saved) {
+                points += if is_complete(opponent_saved) {
+                    combo_points
+                } else {
+                    combo_points * 2
+                };
+            }
+        }
+
+        points
+    }
+
+    // should return true if all types of creatures for the provided colors have been scanned
+    pub fn has_scanned_all_creatures_of_color_for(&self, color: Color, drone_id: i32) -> bool {
+        CreatureKind::FISH.iter().all(|kind| {
+            self.creatures.values().any(|creature| {
+                creature.color == Some(color)
+                    && creature.kind == *kind
+                    && self.scans.contains(&(drone_id, creature.id))
+            })
+        })
+    }
+
+    // should return true if all colors of creatures for the provided type have been scanned
+    pub fn has_scanned_one_of_each_for(&self, kind: CreatureKind, drone_id: i32) -> bool {
+        Color::ALL.iter().all(|color| {
+            self.creatures.values().any(|creature| {
+                creature.kind == kind
+                    && creature.color == Some(*color)
+                    && self.scans.contains(&(drone_id, creature.id))
+            })
+        })
+    }
+}
@@ -0,0 +1,830 @@
+// Choice of each drone's move on top of the fish assignment:
+// endgame planner, opening book, surfacing race, scaring, search and monster avoidance
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    protocol::Move,
+    simulator::{closest_approach, predict_monster, step_toward},
+    state::{
+        GameState, FISH_FLEE_RADIUS, FISH_FLEE_SPEED, LIGHT_BASE_RADIUS, LIGHT_BATTERY_COST,
+        LIGHT_POWER_RADIUS, MAP_SIZE, MAX_TURNS, MONSTER_ATTACK_RADIUS, MOVE_SPEED, SINK_SPEED,
+        SURFACE_Y,
+    },
+    trace::{DroneTrace, Overlay, TraceLevel},
+};
+
+pub const ENDGAME_TURNS: i32 = 20;
+pub const ENDGAME_MAX_UNSCANNED: usize = 3;
+pub const SCARE_HORIZON: i32 = 10;
+pub const SCARE_MIN_POINTS: i32 = 2;
+pub const MONSTER_SAFETY_MARGIN: f64 = 100.0;
+pub const PATH_HORIZON: usize = 3;
+pub const PATH_HEADINGS: usize = 16;
+
+// Chasing a fish off the map so that the foe can never scan it
+#[derive(Clone, Debug)]
+pub struct ScarePlan {
+    pub turns: i32,
+    pub denied_points: i32,
+    pub first_move: Move,
+}
+
+// Unscanned fish split between my drones so that they do not go after the same ones.
+// Each route is the order in which the drone should visit its fish.
+#[derive(Clone, Debug, Default)]
+pub struct Assignment {
+    pub routes: HashMap<i32, Vec<i32>>,
+    // Drone chasing each fish that could be scared off the map, the closest one
+    pub scarers: HashMap<i32, i32>,
+    // Fish left to scan and fish with a known position when the routes were computed
+    pub targets: Vec<i32>,
+    pub sighted: Vec<i32>,
+}
+
+// Where a drone following an endgame plan can be: not before `turns` from now,
+// and then within `radius` of (x, y) plus MOVE_SPEED for each turn after
+#[derive(Clone, Copy, Debug)]
+pub struct PlanReach {
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    pub turns: f64,
+}
+
+// Step of a drone plan used by the endgame planner
+#[derive(Clone, Debug)]
+pub enum Waypoint {
+    Scan(i32),
+    Surface,
+}
+
+impl GameState {
+    // Strategy: follow the opening book in the first turns, go up once the race analysis
+    // says waiting would cost points and until the scans are saved, otherwise keep
+    // searching for scans
+    pub fn choose_move(
+        &self,
+        drone_id: i32,
+        deadline: Option<Instant>,
+        trace: &mut DroneTrace,
+    ) -> Option<Move> {
+        let drone = self.my_drones.get(&drone_id).unwrap();
+        let saved = self.saved_creatures_for(true);
+        let carries_scans = !self
+            .unsaved_creatures_for_drone(drone_id, &saved)
+            .is_empty();
+
+        if self.is_endgame(drone_id) {
+            trace.strategy = "endgame".to_string();
+            return self.plan_endgame(drone_id, deadline);
+        }
+
+        if let Some(m) = self.book_move(drone_id) {
+            trace.strategy = "book".to_string();
+            return Some(m);
+        }
+
+        if self.is_racing && carries_scans {
+            trace.strategy = "race".to_string();
+            return Some(Move {
+                should_move: true,
+                x: Some(drone.x),
+                y: Some(0),
+                light: false,
+            });
+        }
+
+        if let Some(scare) = self.plan_scare(drone_id) {
+            trace.strategy = format!("scare{}", scare.denied_points);
+            return Some(scare.first_move);
+        }
+
+        trace.strategy = "search".to_string();
+        self.find_best_move(drone_id, deadline, trace)
+    }
+
+    // Moves for each of my drones, in the order expected by the game,
+    // along with the debug message to show next to them
+    pub fn next_moves(&self) -> Vec<(i32, Option<Move>, String)> {
+        let started = Instant::now();
+        let mut drone_ids = self.my_drones.keys().copied().collect::<Vec<_>>();
+        drone_ids.sort();
+        let drone_count = drone_ids.len();
+        let budget = (self.params.search_time > 0.0)
+            .then(|| started + Duration::from_secs_f64(self.params.search_time / 1000.0));
+
+        let mut traces = Vec::new();
+        let moves = drone_ids
+            .into_iter()
+            .enumerate()
+            .map(|(i, drone_id)| {
+                // The time left is shared between the drones still to move
+                let deadline = budget.map(|budget| {
+                    let now = Instant::now();
+                    now + budget.saturating_duration_since(now) / (drone_count - i) as u32
+                });
+                let mut trace = DroneTrace::default();
+                let mut overlay = Overlay::default();
+                let m = self.choose_move(drone_id, deadline, &mut trace);
+                overlay.add("S", &trace.strategy);
+                self.track_status(&mut overlay);
+                let m = self.avoid_monsters(drone_id, m, &mut overlay);
+                traces.push((drone_id, m.clone(), trace));
+                (drone_id, m, overlay.message())
+            })
+            .collect();
+
+        if self.trace_level > TraceLevel::Off {
+            eprintln!("{}", self.turn_trace(&traces, started));
+        }
+        moves
+    }
+
+    // Greedy auction: the cheapest (drone, fish) pair is assigned first, the cost being
+    // the turns the drone already has on its route plus the turns to reach the fish
+    // from the end of that route. Fish are then shared between drones and depth bands,
+    // since a drone that goes deep gets more expensive for the shallow fish.
+    pub fn compute_routes(&self, targets: &[i32]) -> HashMap<i32, Vec<i32>> {
+        let mut routes = HashMap::new();
+        let mut route_ends = HashMap::new();
+        let mut route_turns = HashMap::new();
+        for drone in self.my_drones.values() {
+            routes.insert(drone.id, Vec::new());
+            route_ends.insert(drone.id, (drone.x as f64, drone.y as f64));
+            route_turns.insert(drone.id, 0.0);
+        }
+
+        let mut remaining = targets.to_vec();
+        while !remaining.is_empty() {
+            let mut best: Option<(i32, usize, f64)> = None;
+
+            // Drones in id order, so that ties go to the same drone whatever
+            // the order of the map
+            let mut ends = route_ends.iter().collect::<Vec<_>>();
+            ends.sort_unstable_by_key(|(drone_id, _)| **drone_id);
+            for (drone_id, (end_x, end_y)) in ends {
+                for (i, creature_id) in remaining.iter().enumerate() {
+                    let (x, y) = self.estimated_position(*creature_id, *end_x);
+                    let distance = ((x - end_x).powf(2.0) + (y - end_y).powf(2.0)).sqrt();
+                    let turns = f64::max(0.0, distance - LIGHT_BASE_RADIUS) / MOVE_SPEED;
+                    let cost = route_turns.get(drone_id).unwrap() + turns;
+
+                    if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                        best = Some((*drone_id, i, cost));
+                    }
+                }
+            }
+
+            let Some((drone_id, i, cost)) = best else {
+                break;
+            };
+            let creature_id = remaining.remove(i);
+            let end_x = route_ends.get(&drone_id).unwrap().0;
+            route_ends.insert(drone_id, self.estimated_position(creature_id, end_x));
+            route_turns.insert(drone_id, cost);
+            routes.get_mut(&drone_id).unwrap().push(creature_id);
+        }
+
+        routes
+    }
+
+    // Closest of my drones to each fish that could be scared off the map, so that
+    // a single drone goes after it while the others keep to their routes
+    pub fn compute_scarers(&self) -> HashMap<i32, i32> {
+        let mut drones = self.my_drones.values().collect::<Vec<_>>();
+        drones.sort_unstable_by_key(|drone| drone.id);
+
+        self.scare_candidates()
+            .into_iter()
+            .filter_map(|creature_id| {
+                let creature = self.creatures.get(&creature_id).unwrap();
+                let (x, y) = (creature.x? as f64, creature.y? as f64);
+                let drone = drones.iter().min_by(|a, b| {
+                    a.distance_from(x, y)
+                        .partial_cmp(&b.distance_from(x, y))
+                        .unwrap()
+                })?;
+                Some((creature_id, drone.id))
+            })
+            .collect()
+    }
+
+    // Plan again only when a fish was scanned or sighted, so that drones
+    // stick to their targets instead of swapping them every turn
+    pub fn update_assignment(&mut self) {
+        let targets = self.unscanned_creatures_for(true);
+        let mut sighted = self
+            .creatures
+            .values()
+            .filter(|creature| creature.x.is_some())
+            .map(|creature| creature.id)
+            .collect::<Vec<_>>();
+        sighted.sort();
+
+        let has_new_drones = self
+            .my_drones
+            .keys()
+            .any(|drone_id| !self.assignment.routes.contains_key(drone_id));
+        if targets == self.assignment.targets
+            && sighted == self.assignment.sighted
+            && !has_new_drones
+        {
+            return;
+        }
+
+        self.assignment = Assignment {
+            routes: self.compute_routes(&targets),
+            scarers: self.compute_scarers(),
+            targets,
+            sighted,
+        };
+    }
+
+    // Once the race says to go up, the drones keep going until their scans are saved,
+    // rather than diving again as soon as the turn that decided the race is past
+    pub fn update_race(&mut self) {
+        let race = self.race_report();
+        self.is_racing = race.my_unsaved_count > 0 && (self.is_racing || race.should_surface());
+    }
+
+    // Monsters with a known position as (x, y, vx, vy)
+    pub fn known_monsters(&self) -> Vec<(f64, f64, f64, f64)> {
+        self.creatures
+            .values()
+            .filter(|creature| !creature.kind.is_fish())
+            .filter_map(|creature| {
+                Some((
+                    creature.x? as f64,
+                    creature.y? as f64,
+                    creature.vx? as f64,
+                    creature.vy? as f64,
+                ))
+            })
+            .collect()
+    }
+
+    // Depth first search over the sampled headings, keeping the path that ends closest to
+    // the target without going through any monster's attack radius. Monsters notice the drone
+    // within the light radius of its first step, the light being off for the next ones.
+    pub fn search_path(
+        position: (f64, f64),
+        target: (f64, f64),
+        light_radius: f64,
+        monsters: &[(f64, f64, f64, f64)],
+        path: &mut Vec<(f64, f64)>,
+        best: &mut (f64, Vec<(f64, f64)>),
+    ) {
+        let distance =
+            ((target.0 - position.0).powf(2.0) + (target.1 - position.1).powf(2.0)).sqrt();
+        if path.len() == PATH_HORIZON || distance == 0.0 {
+            if distance < best.0 {
+                *best = (distance, path.clone());
+            }
+            return;
+        }
+
+        let mut candidates = vec![step_toward(position, target)];
+        for i in 0..PATH_HEADINGS {
+            let angle = i as f64 * std::f64::consts::TAU / PATH_HEADINGS as f64;
+            candidates.push((
+                position.0 + angle.cos() * MOVE_SPEED,
+                position.1 + angle.sin() * MOVE_SPEED,
+            ));
+        }
+
+        for (x, y) in candidates {
+            let next = (
+                f64::max(0.0, f64::min(MAP_SIZE as f64, x)),
+                f64::max(0.0, f64::min(MAP_SIZE as f64, y)),
+            );
+            let is_safe = monsters.iter().all(|(monster_x, monster_y, vx, vy)| {
+                let monster_from = (*monster_x, *monster_y);
+                let monster_to = (monster_x + vx, monster_y + vy);
+                closest_approach(position, next, monster_from, monster_to)
+                    > MONSTER_ATTACK_RADIUS + MONSTER_SAFETY_MARGIN
+            });
+            if !is_safe {
+                continue;
+            }
+
+            let predicted_monsters = monsters
+                .iter()
+                .map(|monster| predict_monster(*monster, next, light_radius))
+                .collect::<Vec<_>>();
+            path.push(next);
+            GameState::search_path(
+                next,
+                target,
+                LIGHT_BASE_RADIUS,
+                &predicted_monsters,
+                path,
+                best,
+            );
+            path.pop();
+        }
+    }
+
+    // Safe waypoints toward the target, one per turn for the next PATH_HORIZON turns at most,
+    // none when the drone is already there. When every path crosses a monster, the single step
+    // getting the furthest from them is returned.
+    pub fn plan_path(&self, drone_id: i32, target: (f64, f64), light: bool) -> Vec<(i32, i32)> {
+        let drone = self.drone(drone_id);
+        let position = (drone.x as f64, drone.y as f64);
+        let monsters = self.known_monsters();
+        let light_radius = if light && drone.battery >= LIGHT_BATTERY_COST {
+            LIGHT_POWER_RADIUS
+        } else {
+            LIGHT_BASE_RADIUS
+        };
+
+        let mut best = (f64::INFINITY, Vec::new());
+        GameState::search_path(
+            position,
+            target,
+            light_radius,
+            &monsters,
+            &mut Vec::new(),
+            &mut best,
+        );
+
+        if best.1.is_empty() && best.0 > 0.0 {
+            let mut furthest = (f64::NEG_INFINITY, position);
+            for i in 0..PATH_HEADINGS {
+                let angle = i as f64 * std::f64::consts::TAU / PATH_HEADINGS as f64;
+                let next = (
+                    f64::max(
+                        0.0,
+                        f64::min(MAP_SIZE as f64, position.0 + angle.cos() * MOVE_SPEED),
+                    ),
+                    f64::max(
+                        0.0,
+                        f64::min(MAP_SIZE as f64, position.1 + angle.sin() * MOVE_SPEED),
+                    ),
+                );
+                let approach = monsters
+                    .iter()
+                    .map(|(monster_x, monster_y, vx, vy)| {
+                        let monster_to = (monster_x + vx, monster_y + vy);
+                        closest_approach(position, next, (*monster_x, *monster_y), monster_to)
+                    })
+                    .fold(f64::INFINITY, f64::min);
+                if approach > furthest.0 {
+                    furthest = (approach, next);
+                }
+            }
+            best.1 = vec![furthest.1];
+        }
+
+        best.1
+            .into_iter()
+            .map(|(x, y)| (x.round() as i32, y.round() as i32))
+            .collect()
+    }
+
+    // Replace the move by the first waypoint of a safe path when going straight
+    // to its target would get the drone caught by a monster
+    pub fn avoid_monsters(
+        &self,
+        drone_id: i32,
+        m: Option<Move>,
+        overlay: &mut Overlay,
+    ) -> Option<Move> {
+        let m = m?;
+        if self.known_monsters().is_empty() {
+            return Some(m);
+        }
+
+        let drone = self.drone(drone_id);
+        let position = (drone.x as f64, drone.y as f64);
+        let target = if m.should_move {
+            (m.x.unwrap() as f64, m.y.unwrap() as f64)
+        } else {
+            (position.0, position.1 + SINK_SPEED)
+        };
+
+        let Some(&(x, y)) = self.plan_path(drone_id, target, m.light).first() else {
+            return Some(m);
+        };
+        let (direct_x, direct_y) = if m.should_move {
+            step_toward(position, target)
+        } else {
+            target
+        };
+        if (x as f64 - direct_x).abs() < 1.0 && (y as f64 - direct_y).abs() < 1.0 {
+            return Some(m);
+        }
+
+        overlay.add("P", "detour");
+        Some(Move {
+            should_move: true,
+            x: Some(x),
+            y: Some(y),
+            light: m.light,
+        })
+    }
+
+    // The endgame plans take over from the search when the game is almost over, or when
+    // the few fish left to scan all have a known position the drone can reach
+    pub fn is_endgame(&self, drone_id: i32) -> bool {
+        let unscanned = self.unscanned_creatures_for(true);
+        self.turn >= MAX_TURNS - ENDGAME_TURNS
+            || (unscanned.len() <= ENDGAME_MAX_UNSCANNED
+                && self.endgame_targets(drone_id).len() == unscanned.len())
+    }
+
+    // Unscanned fish with a known position the drone can reach before the end of the game,
+    // closest first
+    pub fn endgame_targets(&self, drone_id: i32) -> Vec<i32> {
+        let drone = self.drone(drone_id);
+        let start = PlanReach {
+            x: drone.x as f64,
+            y: drone.y as f64,
+            radius: 0.0,
+            turns: 0.0,
+        };
+        let mut targets = self
+            .unscanned_creatures_for(drone.is_mine)
+            .into_iter()
+            .filter_map(|creature_id| {
+                let reach = self.scan_reach(start, creature_id)?;
+                (reach.turns <= (MAX_TURNS - self.turn) as f64)
+                    .then_some((creature_id, reach.turns))
+            })
+            .collect::<Vec<_>>();
+        targets.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+        targets
+            .into_iter()
+            .map(|(creature_id, _)| creature_id)
+            .collect()
+    }
+
+    // Earliest the drone can scan the fish, which moves FISH_FLEE_SPEED a turn at most:
+    // the drone and the fish close the gap by their summed speeds at most. The drone is
+    // then within the light radius of wherever the fish got to.
+    pub fn scan_reach(&self, from: PlanReach, creature_id: i32) -> Option<PlanReach> {
+        let creature = self.creatures.get(&creature_id).unwrap();
+        let (x, y) = (creature.x? as f64, creature.y? as f64);
+        let distance = ((x - from.x).powf(2.0) + (y - from.y).powf(2.0)).sqrt();
+        let gap = distance - from.radius - LIGHT_BASE_RADIUS;
+        let turns = f64::max(
+            from.turns,
+            (gap + MOVE_SPEED * from.turns) / (MOVE_SPEED + FISH_FLEE_SPEED),
+        );
+        Some(PlanReach {
+            x,
+            y,
+            radius: LIGHT_BASE_RADIUS + FISH_FLEE_SPEED * turns,
+            turns,
+        })
+    }
+
+    // Earliest the drone can be at the surface, anywhere above the area it may be in
+    // by then since it can move sideways on the way
+    pub fn surface_reach(from: PlanReach) -> PlanReach {
+        let turns = from.turns + f64::max(0.0, from.y - from.radius - SURFACE_Y) / MOVE_SPEED;
+        PlanReach {
+            x: from.x,
+            y: 0.0,
+            radius: from.radius + SURFACE_Y + MOVE_SPEED * (turns - from.turns),
+            turns,
+        }
+    }
+
+    // Visit every plan scanning exactly `scans` of the targets, in every order and surfacing
+    // or not after each scan, until `visit` returns false. Plans that cannot be completed
+    // before the end of the game, even by the bounds of `scan_reach` and `surface_reach`,
+    // are left out: the waypoints out of reach change nothing to the scans, so the plan
+    // cannot score differently from the one without them, which is visited.
+    pub fn visit_endgame_plans(
+        &self,
+        plan: &mut Vec<Waypoint>,
+        reach: PlanReach,
+        targets: &[i32],
+        scans: usize,
+        carries_scans: bool,
+        visit: &mut impl FnMut(&[Waypoint]) -> bool,
+    ) -> bool {
+        let turns_left = (MAX_TURNS - self.turn) as f64;
+        if scans == 0 && !visit(plan) {
+            return false;
+        }
+
+        if carries_scans && !matches!(plan.last(), Some(Waypoint::Surface)) {
+            let surfaced = GameState::surface_reach(reach);
+            if surfaced.turns <= turns_left {
+                plan.push(Waypoint::Surface);
+                let completed =
+                    self.visit_endgame_plans(plan, surfaced, targets, scans, carries_scans, visit);
+                plan.pop();
+                if !completed {
+                    return false;
+                }
+            }
+        }
+
+        if scans == 0 {
+            return true;
+        }
+        for (i, creature_id) in targets.iter().enumerate() {
+            let Some(scanned) = self.scan_reach(reach, *creature_id) else {
+                continue;
+            };
+            if scanned.turns > turns_left {
+                continue;
+            }
+            let mut remaining = targets.to_vec();
+            remaining.remove(i);
+            plan.push(Waypoint::Scan(*creature_id));
+            let completed =
+                self.visit_endgame_plans(plan, scanned, &remaining, scans - 1, true, visit);
+            plan.pop();
+            if !completed {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Move toward the first waypoint of the plan that is not reached yet
+    pub fn plan_move(&self, drone_id: i32, plan: &mut Vec<Waypoint>) -> Move {
+        let drone = self.drone(drone_id);
+
+        while let Some(waypoint) = plan.first() {
+            match waypoint {
+                Waypoint::Surface => {
+                    if drone.y as f64 <= SURFACE_Y {
+                        plan.remove(0);
+                        continue;
+                    }
+                    return Move {
+                        should_move: true,
+                        x: Some(drone.x),
+                        y: Some(0),
+                        light: false,
+                    };
+                }
+                Waypoint::Scan(creature_id) => {
+                    let creature = self.creatures.get(creature_id).unwrap();
+                    if creature.x.is_none()
+                        || self.drone_scans.contains(&(drone_id, *creature_id))
+                        || self
+                            .saved_creatures_for(drone.is_mine)
+                            .contains(creature_id)
+                    {
+                        plan.remove(0);
+                        continue;
+                    }
+                    return Move {
+                        should_move: true,
+                        x: creature.x,
+                        y: creature.y,
+                        light: drone.battery >= LIGHT_BATTERY_COST
+                            && drone.is_near_creature_with_power(creature),
+                    };
+                }
+            }
+        }
+
+        Move {
+            should_move: false,
+            x: None,
+            y: None,
+            light: false,
+        }
+    }
+
+    // Follow the plans until they are all completed or the game is over, and return
+    // the final score difference along with the turns the drone holds unsaved scans,
+    // counted until the end of the game when it never saves them
+    pub fn simulate_plans(&self, plans: &HashMap<i32, Vec<Waypoint>>, drone_id: i32) -> (i32, i32) {
+        let mut state = self.clone();
+        let mut plans = plans.clone();
+        let mut holding_turns = 0;
+        let holds_scans = |state: &GameState| {
+            let saved = state.saved_creatures_for(true);
+            !state
+                .unsaved_creatures_for_drone(drone_id, &saved)
+                .is_empty()
+        };
+
+        while !state.is_terminal() && plans.values().any(|plan| !plan.is_empty()) {
+            if holds_scans(&state) {
+                holding_turns += 1;
+            }
+            let mut moves = HashMap::new();
+            for (drone_id, plan) in plans.iter_mut() {
+                moves.insert(*drone_id, state.plan_move(*drone_id, plan));
+            }
+            state.apply_moves(&moves);
+        }
+        if holds_scans(&state) {
+            holding_turns += MAX_TURNS - state.turn;
+        }
+
+        let (my_final_score, foe_final_score) = state.final_scores();
+        (my_final_score - foe_final_score, holding_turns)
+    }
+
+    // Search over the drone's scans and surfacing turns: every ordering of the reachable
+    // targets, surfacing or not after each scan, with plans scanning fewer fish tried first.
+    // The foe drones either surface right away or stay, and my other drones surface with
+    // their scans. The plan maximizing the worst final score difference is played, saving
+    // the earliest on ties, or the best one so far when the deadline passes.
+    pub fn plan_endgame(&self, drone_id: i32, deadline: Option<Instant>) -> Option<Move> {
+        let my_saved = self.saved_creatures_for(true);
+        let foe_saved = self.saved_creatures_for(false);
+
+        let mut fixed_plans = HashMap::new();
+        for other_drone_id in self.my_drones.keys() {
+            if *other_drone_id == drone_id {
+                continue;
+            }
+            let carries_scans = !self
+                .unsaved_creatures_for_drone(*other_drone_id, &my_saved)
+                .is_empty();
+            let plan = if carries_scans {
+                vec![Waypoint::Surface]
+            } else {
+                vec![]
+            };
+            fixed_plans.insert(*other_drone_id, plan);
+        }
+
+        let mut foe_plan_sets: Vec<HashMap<i32, Vec<Waypoint>>> = vec![HashMap::new()];
+        for foe_drone_id in self.their_drones.keys() {
+            let mut options = vec![vec![]];
+            if !self
+                .unsaved_creatures_for_drone(*foe_drone_id, &foe_saved)
+                .is_empty()
+            {
+                options.push(vec![Waypoint::Surface]);
+            }
+
+            let mut new_plan_sets = Vec::new();
+            for plan_set in foe_plan_sets.iter() {
+                for option in options.iter() {
+                    let mut new_plan_set = plan_set.clone();
+                    new_plan_set.insert(*foe_drone_id, option.clone());
+                    new_plan_sets.push(new_plan_set);
+                }
+            }
+            foe_plan_sets = new_plan_sets;
+        }
+
+        let drone = self.drone(drone_id);
+        let start = PlanReach {
+            x: drone.x as f64,
+            y: drone.y as f64,
+            radius: 0.0,
+            turns: 0.0,
+        };
+        let carries_scans = !self
+            .unsaved_creatures_for_drone(drone_id, &my_saved)
+            .is_empty();
+        let targets = self.endgame_targets(drone_id);
+
+        let mut best_plan: Option<Vec<Waypoint>> = None;
+        // Final score difference, then the turns holding unsaved scans negated
+        let mut best_score = (i32::MIN, i32::MIN);
+        let mut evaluate = |plan: &[Waypoint]| {
+            if best_plan.is_some() && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            let mut worst_score = (i32::MAX, i32::MAX);
+            for foe_plan_set in foe_plan_sets.iter() {
+                let mut plans = fixed_plans.clone();
+                plans.extend(foe_plan_set.clone());
+                plans.insert(drone_id, plan.to_vec());
+
+                let (score, holding_turns) = self.simulate_plans(&plans, drone_id);
+                worst_score = worst_score.min((score, -holding_turns));
+                if worst_score <= best_score {
+                    break;
+                }
+            }
+
+            if worst_score > best_score {
+                best_score = worst_score;
+                best_plan = Some(plan.to_vec());
+            }
+            true
+        };
+
+        for scans in 0..=targets.len() {
+            let completed = self.visit_endgame_plans(
+                &mut vec![],
+                start,
+                &targets,
+                scans,
+                carries_scans,
+                &mut evaluate,
+            );
+            if !completed {
+                break;
+            }
+        }
+
+        best_plan.map(|mut plan| self.plan_move(drone_id, &mut plan))
+    }
+
+    // Fish we have scanned that the foe has not, still on the map at a known position
+    pub fn scare_candidates(&self) -> Vec<i32> {
+        let my_scanned: HashSet<i32> = self
+            .saved_creatures_for(true)
+            .union(&self.unsaved_creatures_for(true))
+            .copied()
+            .collect();
+        let foe_unscanned = self.unscanned_creatures_for(false);
+
+        foe_unscanned
+            .into_iter()
+            .filter(|creature_id| my_scanned.contains(creature_id))
+            .filter(|creature_id| self.creatures.get(creature_id).unwrap().x.is_some())
+            .collect()
+    }
+
+    // Stand on the far side of the fish so that it flees toward the closest side of the map
+    pub fn scare_move(&self, creature_id: i32) -> Move {
+        let creature = self.creatures.get(&creature_id).unwrap();
+        let x = creature.x.unwrap();
+        let offset = (FISH_FLEE_RADIUS / 2.0) as i32;
+        let target_x = if x < MAP_SIZE / 2 {
+            x + offset
+        } else {
+            x - offset
+        };
+
+        Move {
+            should_move: true,
+            x: Some(i32::max(0, i32::min(MAP_SIZE, target_x))),
+            y: creature.y,
+            light: false,
+        }
+    }
+
+    // Simulate pushing each candidate assigned to the drone toward the edge, my other drones
+    // following their routes, and keep the most valuable fish that leaves the map within
+    // the horizon
+    pub fn plan_scare(&self, drone_id: i32) -> Option<ScarePlan> {
+        let mut best_plan: Option<ScarePlan> = None;
+
+        for creature_id in self.scare_candidates() {
+            if self.assignment.scarers.get(&creature_id) != Some(&drone_id) {
+                continue;
+            }
+            let denied_points = self.creatures.get(&creature_id).unwrap().get_score();
+            if denied_points < SCARE_MIN_POINTS {
+                continue;
+            }
+
+            let first_move = self.scare_move(creature_id);
+            let mut state = self.clone();
+            let mut m = first_move.clone();
+            let mut routes = self
+                .my_drones
+                .keys()
+                .filter(|other_drone_id| **other_drone_id != drone_id)
+                .map(|other_drone_id| {
+                    let route = self.assignment.routes.get(other_drone_id);
+                    let plan = route
+                        .into_iter()
+                        .flatten()
+                        .map(|target_id| Waypoint::Scan(*target_id))
+                        .collect::<Vec<_>>();
+                    (*other_drone_id, plan)
+                })
+                .collect::<Vec<_>>();
+
+            for turns in 1..=SCARE_HORIZON {
+                let mut moves = HashMap::from([(drone_id, m)]);
+                for (other_drone_id, plan) in routes.iter_mut() {
+                    moves.insert(*other_drone_id, state.plan_move(*other_drone_id, plan));
+                }
+                state.apply_moves(&moves);
+
+                if state.departed_creatures.contains(&creature_id) {
+                    let is_better = best_plan.as_ref().is_none_or(|plan| {
+                        denied_points > plan.denied_points
+                            || (denied_points == plan.denied_points && turns < plan.turns)
+                    });
+                    if is_better {
+                        best_plan = Some(ScarePlan {
+                            turns,
+                            denied_points,
+                            first_move: first_move.clone(),
+                        });
+                    }
+                    break;
+                }
+
+                m = state.scare_move(creature_id);
+            }
+        }
+
+        best_plan
+    }
+}
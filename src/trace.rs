@@ -0,0 +1,207 @@
+// Debug output: messages appended to the actions and JSON traces on stderr
+
+use std::{
+    env,
+    io::{self, BufRead, Read},
+    time::Instant,
+};
+
+use crate::{protocol::Move, state::GameState, tracker::Visibility};
+
+// Status text appended to the actions, shown in the replay viewer
+pub const DEBUG_MESSAGES: bool = true;
+pub const MESSAGE_MAX_LENGTH: usize = 40;
+// Input stream printed on stderr as it is read, so that the replay of an online match
+// shows it and the match can be checked against the forward model
+pub const ECHO_INPUTS: bool = false;
+
+// Input that prints every line read on stderr when `echo` is on
+pub struct EchoInput<R> {
+    pub inner: R,
+    pub echo: bool,
+}
+impl<R: BufRead> Read for EchoInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.fill_buf()?.read(buf)?;
+        self.consume(count);
+        Ok(count)
+    }
+}
+impl<R: BufRead> BufRead for EchoInput<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if self.echo {
+            if let Ok(buffer) = self.inner.fill_buf() {
+                eprint!("{}", String::from_utf8_lossy(&buffer[..amount]));
+            }
+        }
+        self.inner.consume(amount);
+    }
+}
+
+// Short status text each component (strategy, tracker, planner) attaches to a drone's action
+#[derive(Default)]
+pub struct Overlay {
+    parts: Vec<String>,
+}
+impl Overlay {
+    pub fn add(&mut self, component: &str, text: &str) {
+        if DEBUG_MESSAGES {
+            self.parts.push(format!("{}:{}", component, text));
+        }
+    }
+
+    // Message cut to the length limit
+    pub fn message(&self) -> String {
+        self.parts
+            .join(" ")
+            .chars()
+            .take(MESSAGE_MAX_LENGTH)
+            .collect()
+    }
+}
+
+// How much is written to stderr every turn, set with FALL2023_TRACE
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum TraceLevel {
+    Off,
+    // State summary, chosen moves and time used
+    Turn,
+    // Also the candidate moves with their scores and the evaluation terms
+    Search,
+}
+impl TraceLevel {
+    pub fn parse(value: &str) -> Result<TraceLevel, String> {
+        match value.trim() {
+            "" | "0" | "off" => Ok(TraceLevel::Off),
+            "1" | "turn" => Ok(TraceLevel::Turn),
+            "2" | "search" => Ok(TraceLevel::Search),
+            _ => Err(format!("unknown trace level `{}`", value)),
+        }
+    }
+
+    pub fn load() -> TraceLevel {
+        let value = env::var("FALL2023_TRACE").unwrap_or_default();
+        TraceLevel::parse(&value).unwrap_or_else(|error| panic!("invalid trace level: {}", error))
+    }
+}
+
+// What led to a drone's move, reported in the trace
+#[derive(Default)]
+pub struct DroneTrace {
+    pub strategy: String,
+    // Deepest search finished, lower than the configured depth when the time ran out
+    pub depth: Option<i32>,
+    // Minimax nodes visited by the search
+    pub nodes: u64,
+    pub candidates: Vec<(Move, f64)>,
+}
+
+pub fn json_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// JSON has no infinity nor NaN
+pub fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+pub fn json_array(items: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(","))
+}
+
+// Values are expected to be JSON already
+pub fn json_object(fields: &[(&str, String)]) -> String {
+    let fields = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{}", json_string(key), value))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(","))
+}
+
+impl GameState {
+    // One JSON line describing the turn, as detailed as the trace level asks
+    pub fn turn_trace(
+        &self,
+        traces: &[(i32, Option<Move>, DroneTrace)],
+        started: Instant,
+    ) -> String {
+        let drones = |is_mine: bool| {
+            let mut drones = self.drones_for(is_mine).values().collect::<Vec<_>>();
+            drones.sort_by_key(|drone| drone.id);
+            json_array(drones.into_iter().map(|drone| {
+                json_object(&[
+                    ("id", drone.id.to_string()),
+                    ("x", drone.x.to_string()),
+                    ("y", drone.y.to_string()),
+                    ("emergency", drone.emergency.to_string()),
+                    ("battery", drone.battery.to_string()),
+                ])
+            }))
+        };
+        let visible_count = self
+            .creatures
+            .values()
+            .filter(|creature| creature.visibility(self.turn) == Visibility::Visible)
+            .count();
+
+        let moves = json_array(traces.iter().map(|(drone_id, m, trace)| {
+            let action = m.as_ref().map_or("WAIT 0".to_string(), Move::to_action);
+            let mut fields = vec![
+                ("drone", drone_id.to_string()),
+                ("strategy", json_string(&trace.strategy)),
+                ("move", json_string(&action)),
+                (
+                    "depth",
+                    trace.depth.map_or("null".to_string(), |d| d.to_string()),
+                ),
+            ];
+            if self.trace_level >= TraceLevel::Search {
+                let candidates = trace.candidates.iter().map(|(m, score)| {
+                    json_object(&[
+                        ("move", json_string(&m.to_action())),
+                        ("score", json_number(*score)),
+                    ])
+                });
+                fields.push(("nodes", trace.nodes.to_string()));
+                fields.push(("candidates", json_array(candidates)));
+            }
+            json_object(&fields)
+        }));
+
+        let mut fields = vec![
+            ("turn", self.turn.to_string()),
+            ("my_score", self.my_score.to_string()),
+            ("foe_score", self.foe_score.to_string()),
+            ("my_drones", drones(true)),
+            ("foe_drones", drones(false)),
+            ("visible_creatures", visible_count.to_string()),
+            (
+                "departed_creatures",
+                self.departed_creatures.len().to_string(),
+            ),
+            ("moves", moves),
+        ];
+        if self.trace_level >= TraceLevel::Search {
+            let mut terms = Vec::new();
+            self.evaluate(Some(&mut terms));
+            let terms = terms
+                .into_iter()
+                .map(|(name, value)| (name, json_number(value)))
+                .collect::<Vec<_>>();
+            fields.push(("evaluation", json_object(&terms)));
+        }
+        fields.push((
+            "time_ms",
+            json_number(started.elapsed().as_secs_f64() * 1000.0),
+        ));
+        json_object(&fields)
+    }
+}
@@ -0,0 +1,202 @@
+// What is known of the creatures out of sight: radar, stale positions and estimates
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    protocol::{RadarBlip, RadarDir},
+    state::{GameState, MAP_SIZE},
+    trace::Overlay,
+};
+
+pub const CONFIDENCE_DECAY: f64 = 0.85;
+pub const MIN_CONFIDENCE: f64 = 0.2;
+// How far off an extrapolated position may be once its confidence is all gone
+pub const MAX_POSITION_DRIFT: f64 = 800.0;
+
+// What is known of a creature's position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Visibility {
+    // Seen this turn
+    Visible,
+    // Seen a few turns ago, its position is extrapolated from its last speed
+    RecentlySeen,
+    // Never seen or seen too long ago, only its habitat and the radar tell where it is
+    Unknown,
+}
+
+// Radar blips of the current turn, one direction per (drone, creature)
+#[derive(Clone, Debug, Default)]
+pub struct Radar {
+    pub blips: HashMap<(i32, i32), RadarDir>,
+}
+impl Radar {
+    pub fn clear(&mut self) {
+        self.blips.clear();
+    }
+
+    pub fn insert(&mut self, blip: RadarBlip) {
+        self.blips
+            .insert((blip.drone_id, blip.creature_id), blip.radar);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blips.is_empty()
+    }
+
+    pub fn direction(&self, drone_id: i32, creature_id: i32) -> Option<RadarDir> {
+        self.blips.get(&(drone_id, creature_id)).copied()
+    }
+
+    // Creatures reported by any drone
+    pub fn creature_ids(&self) -> HashSet<i32> {
+        self.blips
+            .keys()
+            .map(|(_, creature_id)| *creature_id)
+            .collect()
+    }
+
+    // Creatures in the given quadrant of the drone
+    pub fn creatures_in(&self, drone_id: i32, direction: RadarDir) -> Vec<i32> {
+        let mut creature_ids = self
+            .blips
+            .iter()
+            .filter(|((blip_drone_id, _), radar)| {
+                *blip_drone_id == drone_id && **radar == direction
+            })
+            .map(|((_, creature_id), _)| *creature_id)
+            .collect::<Vec<_>>();
+        creature_ids.sort();
+        creature_ids
+    }
+}
+
+impl GameState {
+    // Visible and recently seen creatures out of the ones still in the game
+    pub fn track_status(&self, overlay: &mut Overlay) {
+        let in_game = self
+            .creatures
+            .values()
+            .filter(|creature| !self.departed_creatures.contains(&creature.id))
+            .collect::<Vec<_>>();
+        let count = |visibility: Visibility| {
+            in_game
+                .iter()
+                .filter(|creature| creature.visibility(self.turn) == visibility)
+                .count()
+        };
+
+        overlay.add(
+            "T",
+            &format!(
+                "{}+{}/{}",
+                count(Visibility::Visible),
+                count(Visibility::RecentlySeen),
+                in_game.len()
+            ),
+        );
+    }
+
+    // Positions seen too long ago are dropped, the others are kept inside
+    // the region the radar allows since the extrapolation drifts over time
+    pub fn forget_stale_creatures(&mut self) {
+        let creature_ids = self.creatures.keys().copied().collect::<Vec<_>>();
+        for creature_id in creature_ids {
+            let creature = self.creatures.get(&creature_id).unwrap();
+            match creature.visibility(self.turn) {
+                Visibility::Visible => {}
+                Visibility::RecentlySeen => {
+                    let is_on_radar = self
+                        .my_drones
+                        .keys()
+                        .any(|drone_id| self.radar.direction(*drone_id, creature_id).is_some());
+                    if is_on_radar {
+                        let (min_x, min_y, max_x, max_y) = self.estimated_region(creature_id);
+                        let creature = self.creatures.get_mut(&creature_id).unwrap();
+                        creature.x = creature.x.map(|x| x.clamp(min_x, max_x));
+                        creature.y = creature.y.map(|y| y.clamp(min_y, max_y));
+                    }
+                }
+                Visibility::Unknown => {
+                    let creature = self.creatures.get_mut(&creature_id).unwrap();
+                    creature.x = None;
+                    creature.y = None;
+                    creature.vx = None;
+                    creature.vy = None;
+                }
+            }
+        }
+    }
+
+    // Where to look for the fish: its position when visible, the middle of its
+    // radar region when unknown, or otherwise the middle of its habitat below the given x.
+    // A recently seen position is blended with that guess as its confidence decays.
+    pub fn estimated_position(&self, creature_id: i32, x: f64) -> (f64, f64) {
+        let creature = self.creatures.get(&creature_id).unwrap();
+        let visibility = creature.visibility(self.turn);
+        if let (Visibility::Visible, Some(x), Some(y)) = (visibility, creature.x, creature.y) {
+            return (x as f64, y as f64);
+        }
+
+        let is_on_radar = self
+            .my_drones
+            .keys()
+            .any(|drone_id| self.radar.direction(*drone_id, creature_id).is_some());
+        let (guess_x, guess_y) = if is_on_radar {
+            let (min_x, min_y, max_x, max_y) = self.estimated_region(creature_id);
+            ((min_x + max_x) as f64 / 2.0, (min_y + max_y) as f64 / 2.0)
+        } else {
+            let (min_y, max_y) = creature.habitat();
+            (x, (min_y + max_y) as f64 / 2.0)
+        };
+
+        match (visibility, creature.x, creature.y) {
+            (Visibility::RecentlySeen, Some(x), Some(y)) => {
+                let confidence = creature.confidence(self.turn);
+                (
+                    confidence * x as f64 + (1.0 - confidence) * guess_x,
+                    confidence * y as f64 + (1.0 - confidence) * guess_y,
+                )
+            }
+            _ => (guess_x, guess_y),
+        }
+    }
+
+    // Box (min_x, min_y, max_x, max_y) the creature is in, according to
+    // its habitat and the radar directions from each of my drones
+    pub fn estimated_region(&self, creature_id: i32) -> (i32, i32, i32, i32) {
+        let creature = self.creatures.get(&creature_id).unwrap();
+        let (min_y, max_y) = creature.habitat();
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (0, min_y, MAP_SIZE, max_y);
+
+        for drone in self.my_drones.values() {
+            match self.radar.direction(drone.id, creature_id) {
+                Some(RadarDir::TL) => {
+                    max_x = i32::min(max_x, drone.x);
+                    max_y = i32::min(max_y, drone.y);
+                }
+                Some(RadarDir::TR) => {
+                    min_x = i32::max(min_x, drone.x);
+                    max_y = i32::min(max_y, drone.y);
+                }
+                Some(RadarDir::BL) => {
+                    max_x = i32::min(max_x, drone.x);
+                    min_y = i32::max(min_y, drone.y);
+                }
+                Some(RadarDir::BR) => {
+                    min_x = i32::max(min_x, drone.x);
+                    min_y = i32::max(min_y, drone.y);
+                }
+                None => {}
+            }
+        }
+
+        // Blips disagreeing with the habitat, keep the closest consistent box
+        if min_x > max_x {
+            (min_x, max_x) = (max_x, min_x);
+        }
+        if min_y > max_y {
+            (min_y, max_y) = (max_y, min_y);
+        }
+        (min_x, min_y, max_x, max_y)
+    }
+}
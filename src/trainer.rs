@@ -0,0 +1,222 @@
+use std::thread;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    eval::Params,
+    features::FEATURE_COUNT,
+    referee::Referee,
+    state::{League, Outcome},
+    value_net::{self, ValueNet, HIDDEN_SIZE},
+};
+
+// Every game out of this many is kept aside to validate the network
+const VALIDATION_EVERY: u64 = 5;
+const REPORT_EVERY: usize = 10;
+
+type Sample = ([f32; FEATURE_COUNT], f32);
+
+struct Options {
+    games: u64,
+    epochs: usize,
+    seed: u64,
+    depth: f64,
+    learning_rate: f32,
+    league: League,
+}
+
+fn parse_options(args: Vec<String>) -> Options {
+    let mut options = Options {
+        games: 40,
+        epochs: 5,
+        seed: 0,
+        depth: 1.0,
+        learning_rate: 0.002,
+        league: League::load(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--games" => options.games = value.parse().unwrap(),
+            "--epochs" => options.epochs = value.parse().unwrap(),
+            "--seed" => options.seed = value.parse().unwrap(),
+            "--depth" => options.depth = value.parse().unwrap(),
+            "--learning-rate" => options.learning_rate = value.parse().unwrap(),
+            "--league" => options.league = League::parse(&value).unwrap(),
+            _ => panic!(
+                "unknown option {}, available options: --games --epochs --seed --depth --learning-rate --league",
+                arg
+            ),
+        }
+    }
+
+    options
+}
+
+// Features of what each player knew every turn of a self-play match, with the outcome
+// for that player: 1 for a win, -1 for a loss and 0 for a draw
+fn record_match(seed: u64, league: League, params: Params) -> Vec<Sample> {
+    let mut referee = Referee::new(seed, league);
+    let outcome = match referee.play([params, params]) {
+        Outcome::Win => 1.0,
+        Outcome::Loss => -1.0,
+        Outcome::Draw => 0.0,
+    };
+
+    let mut samples = Vec::new();
+    for frame in referee.frames() {
+        for (player, view) in frame.views.iter().enumerate() {
+            let target = if player == 0 { outcome } else { -outcome };
+            samples.push((view.features(), target));
+        }
+    }
+    samples
+}
+
+// One step of gradient descent on the squared error, returns the squared error
+fn train_sample(net: &mut ValueNet, sample: &Sample, learning_rate: f32) -> f32 {
+    let (features, target) = sample;
+    let (hidden, output) = net.forward(features);
+    let error = output - target;
+    let output_gradient = error * (1.0 - output * output);
+
+    let mut hidden_gradients = [0.0; HIDDEN_SIZE];
+    for (j, neuron) in hidden.iter().enumerate() {
+        if *neuron > 0.0 {
+            hidden_gradients[j] = output_gradient * net.output[j];
+        }
+        net.output[j] -= learning_rate * output_gradient * neuron;
+    }
+    net.output[HIDDEN_SIZE] -= learning_rate * output_gradient;
+
+    for (weights, gradient) in net
+        .hidden
+        .chunks_exact_mut(FEATURE_COUNT + 1)
+        .zip(hidden_gradients)
+    {
+        for (weight, feature) in weights.iter_mut().zip(features) {
+            *weight -= learning_rate * gradient * feature;
+        }
+        weights[FEATURE_COUNT] -= learning_rate * gradient;
+    }
+
+    error * error
+}
+
+// Mean squared error and share of the decided games whose winner is predicted
+fn validate(net: &ValueNet, samples: &[Sample]) -> (f32, f32) {
+    let squared_error = samples
+        .iter()
+        .map(|(features, target)| (net.predict(features) - target).powi(2))
+        .sum::<f32>();
+
+    let decided = samples
+        .iter()
+        .filter(|(_, target)| *target != 0.0)
+        .collect::<Vec<_>>();
+    let correct = decided
+        .iter()
+        .filter(|(features, target)| net.predict(features) * target > 0.0)
+        .count();
+
+    (
+        squared_error / usize::max(1, samples.len()) as f32,
+        correct as f32 / usize::max(1, decided.len()) as f32,
+    )
+}
+
+fn format_weights(net: &ValueNet) -> String {
+    let (hidden_weights, hidden_scale) = value_net::encode(&net.hidden);
+    let (output_weights, output_scale) = value_net::encode(&net.output);
+    format!(
+        "pub const HIDDEN_SCALE: f32 = {:e};\npub const HIDDEN_WEIGHTS: &str = r\"{}\";\npub const OUTPUT_SCALE: f32 = {:e};\npub const OUTPUT_WEIGHTS: &str = r\"{}\";\n",
+        hidden_scale, hidden_weights, output_scale, output_weights
+    )
+}
+
+// Fit the value network on the outcomes of self-play matches between the loaded parameters,
+// then print its quantized weights to paste in value_net.rs
+pub fn run(args: Vec<String>) {
+    let options = parse_options(args);
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut params = Params::load();
+    params.search_depth = options.depth;
+    // Matches are played in parallel, the searches must not depend on the time they get
+    params.search_time = 0.0;
+
+    let seeds = (0..options.games).map(|_| rng.gen()).collect::<Vec<u64>>();
+    let matches = thread::scope(|scope| {
+        let handles = seeds
+            .iter()
+            .map(|seed| scope.spawn(move || record_match(*seed, options.league, params)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let mut training = Vec::new();
+    let mut validation = Vec::new();
+    for (game, samples) in (0..).zip(matches) {
+        if game % VALIDATION_EVERY == VALIDATION_EVERY - 1 {
+            validation.extend(samples);
+        } else {
+            training.extend(samples);
+        }
+    }
+    eprintln!(
+        "{} games recorded, {} training and {} validation samples",
+        options.games,
+        training.len(),
+        validation.len()
+    );
+
+    let mut net = ValueNet::zeros();
+    let hidden_bound = 1.0 / (FEATURE_COUNT as f32).sqrt();
+    let output_bound = 1.0 / (HIDDEN_SIZE as f32).sqrt();
+    net.hidden
+        .iter_mut()
+        .for_each(|weight| *weight = rng.gen_range(-hidden_bound..hidden_bound));
+    net.output
+        .iter_mut()
+        .for_each(|weight| *weight = rng.gen_range(-output_bound..output_bound));
+
+    for epoch in 0..options.epochs {
+        training.shuffle(&mut rng);
+        let squared_error = training
+            .iter()
+            .map(|sample| train_sample(&mut net, sample, options.learning_rate))
+            .sum::<f32>();
+
+        if (epoch + 1) % REPORT_EVERY == 0 || epoch + 1 == options.epochs {
+            let (validation_error, accuracy) = validate(&net, &validation);
+            eprintln!(
+                "epoch {}: training error {:.4}, validation error {:.4}, winner predicted {:.1}%",
+                epoch + 1,
+                squared_error / usize::max(1, training.len()) as f32,
+                validation_error,
+                100.0 * accuracy
+            );
+        }
+    }
+
+    let (hidden_weights, hidden_scale) = value_net::encode(&net.hidden);
+    let (output_weights, output_scale) = value_net::encode(&net.output);
+    let quantized = ValueNet {
+        hidden: value_net::decode(&hidden_weights, hidden_scale),
+        output: value_net::decode(&output_weights, output_scale),
+    };
+    let (validation_error, accuracy) = validate(&quantized, &validation);
+    eprintln!(
+        "quantized: validation error {:.4}, winner predicted {:.1}%",
+        validation_error,
+        100.0 * accuracy
+    );
+
+    print!("{}", format_weights(&net));
+}
@@ -0,0 +1,199 @@
+use std::{fs, thread};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    eval::Params,
+    referee::Referee,
+    state::{League, Outcome},
+};
+
+// SPSA gains, see Spall's "Implementation of the simultaneous perturbation algorithm"
+const LEARNING_RATE: f64 = 2.0;
+const LEARNING_RATE_OFFSET: f64 = 10.0;
+const LEARNING_RATE_DECAY: f64 = 0.602;
+const PERTURBATION: f64 = 1.0;
+const PERTURBATION_DECAY: f64 = 0.101;
+const VALIDATE_EVERY: usize = 10;
+
+// Tuned parameters with the size of one perturbation step and their bounds
+const TUNED_PARAMS: [(&str, f64, f64, f64); 9] = [
+    ("score_weight", 10000.0, 1.0, 10_000_000.0),
+    ("combo_weight", 100.0, 0.0, 100_000.0),
+    ("distance_scale", 150.0, 0.0, 100_000.0),
+    ("distance_log_base", 0.01, 1.001, 2.0),
+    ("distance_offset", 0.5, 0.0, 1000.0),
+    ("distance_shift", 150.0, -100_000.0, 100_000.0),
+    ("potential_weight", 1000.0, 0.0, 1_000_000.0),
+    ("potential_discount", 0.01, 0.5, 1.0),
+    ("search_depth", 0.5, 1.0, 4.0),
+];
+
+struct Options {
+    iterations: usize,
+    games: usize,
+    seed: u64,
+    output: String,
+    league: League,
+}
+
+fn parse_options(args: Vec<String>) -> Options {
+    let mut options = Options {
+        iterations: 100,
+        games: 20,
+        seed: 0,
+        output: "params.txt".to_string(),
+        league: League::load(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("missing value for {}", arg));
+        match arg.as_str() {
+            "--iterations" => options.iterations = value.parse().unwrap(),
+            "--games" => options.games = value.parse().unwrap(),
+            "--seed" => options.seed = value.parse().unwrap(),
+            "--output" => options.output = value,
+            "--league" => options.league = League::parse(&value).unwrap(),
+            _ => panic!(
+                "unknown option {}, available options: --iterations --games --seed --output --league",
+                arg
+            ),
+        }
+    }
+
+    options
+}
+
+// Share of the points won by `params` against `opponent`, each seed being played from both sides.
+// Matches are played in parallel, so the searches have no time limit that would make
+// their depth depend on the CPU they share
+fn play_batch(params: Params, opponent: Params, seeds: &[u64], league: League) -> f64 {
+    let unlimited = |params: Params| Params {
+        search_time: 0.0,
+        ..params
+    };
+    let (params, opponent) = (unlimited(params), unlimited(opponent));
+    let points = thread::scope(|scope| {
+        let handles = seeds
+            .iter()
+            .map(|seed| {
+                scope.spawn(move || {
+                    let first = Referee::new(*seed, league).play([params, opponent]);
+                    let second = Referee::new(*seed, league).play([opponent, params]);
+
+                    let points = |outcome: Outcome, is_first_player: bool| match outcome {
+                        Outcome::Draw => 0.5,
+                        Outcome::Win if is_first_player => 1.0,
+                        Outcome::Loss if !is_first_player => 1.0,
+                        _ => 0.0,
+                    };
+                    points(first, true) + points(second, false)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum::<f64>()
+    });
+
+    points / (2 * seeds.len()) as f64
+}
+
+// Move every tuned parameter by `step` times its step size in the direction of `delta`
+fn perturb(params: Params, delta: &[f64], step: f64) -> Params {
+    let mut perturbed = params;
+    for ((name, step_size, min, max), direction) in TUNED_PARAMS.iter().zip(delta) {
+        let value = params.get(name).unwrap() + step * direction * step_size;
+        perturbed.set(name, value.clamp(*min, *max)).unwrap();
+    }
+    perturbed
+}
+
+// Tune the parameters with SPSA: every iteration plays a batch of self-play games between
+// two opposite random perturbations of the current parameters and moves them toward the winner.
+// The current parameters are regularly validated against the starting ones, and the best
+// ones are always written to the output file along with their win rate, even when they
+// do not beat the starting ones.
+pub fn run(args: Vec<String>) {
+    let options = parse_options(args);
+    let mut rng = StdRng::seed_from_u64(options.seed);
+
+    let start_params = Params::load();
+    let validation_seeds = (0..options.games).map(|_| rng.gen()).collect::<Vec<u64>>();
+    let mut params = start_params;
+    let mut best_win_rate: Option<f64> = None;
+
+    for iteration in 0..options.iterations {
+        let k = iteration as f64 + 1.0;
+        let learning_rate = LEARNING_RATE / (k + LEARNING_RATE_OFFSET).powf(LEARNING_RATE_DECAY);
+        let perturbation = PERTURBATION / k.powf(PERTURBATION_DECAY);
+
+        let delta = TUNED_PARAMS
+            .iter()
+            .map(|_| if rng.gen() { 1.0 } else { -1.0 })
+            .collect::<Vec<_>>();
+        let seeds = (0..options.games).map(|_| rng.gen()).collect::<Vec<u64>>();
+
+        let plus = perturb(params, &delta, perturbation);
+        let minus = perturb(params, &delta, -perturbation);
+        let plus_score = play_batch(plus, minus, &seeds, options.league);
+
+        // Both perturbations play each other, so the minus score is 1 - plus_score
+        let gradient = delta
+            .iter()
+            .map(|direction| (2.0 * plus_score - 1.0) / (2.0 * perturbation * direction))
+            .collect::<Vec<_>>();
+        params = perturb(params, &gradient, learning_rate);
+
+        eprintln!(
+            "iteration {}: perturbations scored {:.3} / {:.3}",
+            iteration + 1,
+            plus_score,
+            1.0 - plus_score
+        );
+
+        if (iteration + 1) % VALIDATE_EVERY == 0 || iteration + 1 == options.iterations {
+            let win_rate = play_batch(params, start_params, &validation_seeds, options.league);
+            eprintln!(
+                "validation: {:.3} against the starting parameters",
+                win_rate
+            );
+
+            if best_win_rate.is_none_or(|best_win_rate| win_rate > best_win_rate) {
+                best_win_rate = Some(win_rate);
+                let config = format!(
+                    "# {:.3} against the starting parameters over {} games, after {} iterations, {}\n{}",
+                    win_rate,
+                    2 * validation_seeds.len(),
+                    iteration + 1,
+                    baseline_verdict(win_rate),
+                    params.to_config()
+                );
+                fs::write(&options.output, config).expect("cannot write the output file");
+                eprintln!("best parameters written to {}", options.output);
+            }
+        }
+    }
+
+    if let Some(win_rate) = best_win_rate {
+        eprintln!(
+            "best validation: {:.3} over {} games, {}",
+            win_rate,
+            2 * validation_seeds.len(),
+            baseline_verdict(win_rate)
+        );
+    }
+}
+
+fn baseline_verdict(win_rate: f64) -> &'static str {
+    if win_rate > 0.5 {
+        "better than the starting parameters"
+    } else {
+        "not better than the starting parameters"
+    }
+}
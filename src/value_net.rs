@@ -0,0 +1,111 @@
+// Small multilayer perceptron estimating the outcome of a state from its features,
+// an alternative to the hand-written evaluation. The weights are written by `train`.
+
+use std::sync::OnceLock;
+
+use crate::{
+    features::FEATURE_COUNT,
+    state::{GameState, PlayerScans},
+};
+
+pub const HIDDEN_SIZE: usize = 16;
+// Weights are quantized to integers within -WEIGHT_LEVELS..=WEIGHT_LEVELS times the scale
+// of their layer, each one written as a printable character
+pub const WEIGHT_LEVELS: i32 = 40;
+const WEIGHT_ZERO: i32 = b'O' as i32;
+
+pub const HIDDEN_SCALE: f32 = 5.42561e-2;
+pub const HIDDEN_WEIGHTS: &str = r"WWVKIQRMTKRRLNQPJROOQRNDHMPJOQQIRPLQNRORMNQLOPRNNMNTMLLNRNPOQMOOONONQKQQOKROVVMPOLPRKOONPOOKRNPNMPPQQNMMNNQMNOOONPNMNONPNMMPPONPPNONNQPMLNPNPOPNKOKMSPOTHSOMWOLQSLMRPLSRQPLGQKKLNOPMOJPRKNIMLPNQMNMNNMLMOQNNPMONMMNQONOQQPMNOPMOOPQROLPPQMPOPLOMSMTEXKJTNOQMKMKQPNOLRKQOPMLSLOMNNOMSTQONMQPOTLOM57GTNPQTFXPIQQMNTPPWXMPjcVMKNJNXJTPJLKQJTSINTLOSROMMOMQNMNNMOOPOPQOOMQONNMLMMOMMQNMNPPOMNPQMLNOL`MG_XP`TGPJKWNJRQSLMOSL;CTOLNESQNSPNNQKJXQNPLNQA`aSUKNJNOMTLLOJMQNLJOIM9@PMKSORK[RLSQMNJOQTVITTM7'MVPPNIVOQOJOMRNUHMQOJroPFOSATWVTPNTPRQNNNKLR[HMONQNNPMOONMQPNQNPMMPMMQOPNPNLMMLMONNOMMOPPOPONPWWUFMM:ZUTPLOSTKSMNROMPIAMRPRLLVLPTHPQPOWKKMPPVI\`NLOZJPXSULPPPPPVMPNMQE9FWOLKJXRPOEMNPPPNIOPMTGC@TLHMJWHMSOKQSPKTNNJLPcRP[RPWTNONLSQLMRQNMOSKQGLRRLONPNQMNPQORLPOMOMQLSLPITMKOPOORNRQQOPRQSMNMM";
+pub const OUTPUT_SCALE: f32 = 4.6021726e-2;
+pub const OUTPUT_WEIGHTS: &str = r"\AK>La9Lng'Lim5BU";
+
+// Each neuron has its weights for every input followed by its bias
+#[derive(Clone, Debug)]
+pub struct ValueNet {
+    pub hidden: Vec<f32>,
+    pub output: Vec<f32>,
+}
+impl ValueNet {
+    pub fn zeros() -> ValueNet {
+        ValueNet {
+            hidden: vec![0.0; HIDDEN_SIZE * (FEATURE_COUNT + 1)],
+            output: vec![0.0; HIDDEN_SIZE + 1],
+        }
+    }
+
+    // Weights compiled in, the network is all zeros until it has been trained
+    pub fn embedded() -> &'static ValueNet {
+        static NET: OnceLock<ValueNet> = OnceLock::new();
+        NET.get_or_init(|| {
+            let mut net = ValueNet::zeros();
+            if !HIDDEN_WEIGHTS.is_empty() {
+                net.hidden = decode(HIDDEN_WEIGHTS, HIDDEN_SCALE);
+                net.output = decode(OUTPUT_WEIGHTS, OUTPUT_SCALE);
+            }
+            net
+        })
+    }
+
+    // Hidden layer activations, then the estimate between -1 for a loss and 1 for a win
+    pub fn forward(&self, features: &[f32]) -> ([f32; HIDDEN_SIZE], f32) {
+        let mut hidden = [0.0; HIDDEN_SIZE];
+        for (neuron, weights) in hidden
+            .iter_mut()
+            .zip(self.hidden.chunks_exact(FEATURE_COUNT + 1))
+        {
+            let sum = weights[FEATURE_COUNT]
+                + features
+                    .iter()
+                    .zip(weights)
+                    .map(|(feature, weight)| feature * weight)
+                    .sum::<f32>();
+            *neuron = f32::max(0.0, sum);
+        }
+
+        let sum = self.output[HIDDEN_SIZE]
+            + hidden
+                .iter()
+                .zip(&self.output)
+                .map(|(neuron, weight)| neuron * weight)
+                .sum::<f32>();
+        (hidden, sum.tanh())
+    }
+
+    pub fn predict(&self, features: &[f32]) -> f32 {
+        self.forward(features).1
+    }
+}
+
+pub fn decode(weights: &str, scale: f32) -> Vec<f32> {
+    weights
+        .bytes()
+        .map(|byte| (byte as i32 - WEIGHT_ZERO) as f32 * scale)
+        .collect()
+}
+
+// Quantized weights with the scale to decode them
+pub fn encode(weights: &[f32]) -> (String, f32) {
+    let max = weights
+        .iter()
+        .fold(0.0, |max: f32, weight| max.max(weight.abs()));
+    let scale = if max > 0.0 {
+        max / WEIGHT_LEVELS as f32
+    } else {
+        1.0
+    };
+    let encoded = weights
+        .iter()
+        .map(|weight| {
+            let level = (weight / scale).round() as i32;
+            (WEIGHT_ZERO + level.clamp(-WEIGHT_LEVELS, WEIGHT_LEVELS)) as u8 as char
+        })
+        .collect();
+    (encoded, scale)
+}
+
+impl GameState {
+    // Estimated outcome of the game, between -1 for a loss and 1 for a win
+    pub fn value_net_estimate(&self, my_scans: &PlayerScans, foe_scans: &PlayerScans) -> f32 {
+        ValueNet::embedded().predict(&self.features_from(my_scans, foe_scans))
+    }
+}
@@ -0,0 +1,49 @@
+use std::{fmt::Write, io::Cursor};
+
+use fall_challenge_2023::{protocol::RadarDir, state::GameState};
+
+const FISH_IDS: std::ops::RangeInclusive<i32> = 4..=15;
+
+// Mid game with every fish scanned by me: all saved but the one my drone carries at the
+// bottom of the map, and the foe drone at the surface with nothing to save
+fn holding_last_scan() -> GameState {
+    let mut input = format!("{}\n", FISH_IDS.count());
+    for id in FISH_IDS {
+        writeln!(input, "{} {} {}", id, (id - 4) / 3, (id - 4) % 3).unwrap();
+    }
+    let mut state = GameState::new(&mut Cursor::new(input));
+
+    let my_saved = FISH_IDS.filter(|id| *id != 4).collect::<Vec<_>>();
+    let turn_input = |saved: &[i32]| {
+        let mut input = format!("0\n0\n{}\n", saved.len());
+        for creature_id in saved {
+            writeln!(input, "{}", creature_id).unwrap();
+        }
+        input += "0\n";
+        input += "1\n0 5000 9500 0 30\n";
+        input += "1\n1 3000 500 0 30\n";
+        input += "1\n0 4\n";
+        input += "0\n";
+        writeln!(input, "{}", FISH_IDS.count()).unwrap();
+        for id in FISH_IDS {
+            let radar = RadarDir::between((5000, 9500), (id * 600, 5000));
+            writeln!(input, "0 {} {}", id, radar.as_str()).unwrap();
+        }
+        input
+    };
+    state.update_state(&mut Cursor::new(turn_input(&[])));
+    state.update_state(&mut Cursor::new(turn_input(&my_saved)));
+    state.turn = 100;
+    state
+}
+
+#[test]
+fn endgame_saves_scans_rather_than_wait_on_a_tie() {
+    let state = holding_last_scan();
+    assert!(state.is_endgame(0));
+
+    // Saving now or at the end of the game scores the same, the drone must not wait
+    let m = state.plan_endgame(0, None).unwrap();
+    assert!(m.should_move);
+    assert_eq!(m.y, Some(0));
+}
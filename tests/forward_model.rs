@@ -0,0 +1,337 @@
+// The referee is local tooling, left out of builds without the `local` feature
+#![cfg(feature = "local")]
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    io::Cursor,
+};
+
+use fall_challenge_2023::{
+    protocol::{Move, RadarDir},
+    referee::Referee,
+    state::{
+        GameState, League, COLOR_COMBO_POINTS, MAP_SIZE, MAX_BATTERY, MIN_BATTERY,
+        TYPE_COMBO_POINTS,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const CASES: u64 = 200;
+const TURNS: usize = 10;
+const MATCHES: u64 = 12;
+const LEAGUES: [League; 3] = [League::Wood2, League::Wood1, League::Bronze];
+const FISH_IDS: std::ops::RangeInclusive<i32> = 4..=15;
+const FIRST_MONSTER_ID: i32 = 16;
+
+// Random but valid game, written as protocol input so that it goes through the bot's parsing:
+// one or two drones per player anywhere on the map, fish and monsters in their habitat,
+// some of them out of sight, and random saved and unsaved scans
+fn random_state(rng: &mut StdRng) -> GameState {
+    let monster_count = rng.gen_range(0..=3);
+    let mut creatures = FISH_IDS
+        .map(|id| (id, (id - 4) / 3, (id - 4) % 3))
+        .collect::<Vec<_>>();
+    creatures.extend((0..monster_count).map(|i| (FIRST_MONSTER_ID + i, -1, -1)));
+
+    let mut input = format!("{}\n", creatures.len());
+    for (id, color, kind) in creatures.iter() {
+        writeln!(input, "{} {} {}", id, color, kind).unwrap();
+    }
+    let mut state = GameState::new(&mut Cursor::new(input));
+
+    let drone_count = rng.gen_range(1..=2);
+    let my_drone_ids = (0..drone_count).map(|i| 2 * i).collect::<Vec<_>>();
+    let foe_drone_ids = (0..drone_count).map(|i| 2 * i + 1).collect::<Vec<_>>();
+    let my_saved = FISH_IDS.filter(|_| rng.gen_bool(0.2)).collect::<Vec<_>>();
+    let foe_saved = FISH_IDS.filter(|_| rng.gen_bool(0.2)).collect::<Vec<_>>();
+
+    let scores = (rng.gen_range(0..=80), rng.gen_range(0..=80));
+
+    let mut drones = Vec::new();
+    for drone_id in my_drone_ids.iter().chain(foe_drone_ids.iter()) {
+        let (x, y) = (rng.gen_range(0..=MAP_SIZE), rng.gen_range(0..=MAP_SIZE));
+        let battery = rng.gen_range(MIN_BATTERY..=MAX_BATTERY);
+        drones.push((*drone_id, x, y, battery));
+    }
+
+    let mut drone_scans = Vec::new();
+    for (drone_id, _, _, _) in drones.iter() {
+        let saved = if drone_id % 2 == 0 {
+            &my_saved
+        } else {
+            &foe_saved
+        };
+        for creature_id in FISH_IDS {
+            if !saved.contains(&creature_id) && rng.gen_bool(0.3) {
+                drone_scans.push((*drone_id, creature_id));
+            }
+        }
+    }
+
+    let positions = creatures
+        .iter()
+        .map(|(id, _, _)| {
+            let (min_y, max_y) = state.creatures.get(id).unwrap().habitat();
+            let speed = if *id >= FIRST_MONSTER_ID { 540 } else { 400 };
+            (
+                *id,
+                rng.gen_range(0..MAP_SIZE),
+                rng.gen_range(min_y..=max_y),
+                rng.gen_range(-speed..=speed),
+                rng.gen_range(-speed..=speed),
+            )
+        })
+        .collect::<Vec<_>>();
+    let visible = positions
+        .iter()
+        .filter(|_| rng.gen_bool(0.7))
+        .collect::<Vec<_>>();
+
+    // Saved scans are credited to a drone already known, so they only come on a second turn
+    let turn_input = |saved_lists: [&[i32]; 2]| {
+        let mut input = format!("{}\n{}\n", scores.0, scores.1);
+        for saved in saved_lists {
+            writeln!(input, "{}", saved.len()).unwrap();
+            for creature_id in saved.iter() {
+                writeln!(input, "{}", creature_id).unwrap();
+            }
+        }
+
+        for parity in [0, 1] {
+            let player_drones = drones.iter().filter(|(id, _, _, _)| id % 2 == parity);
+            writeln!(input, "{}", player_drones.clone().count()).unwrap();
+            for (drone_id, x, y, battery) in player_drones {
+                writeln!(input, "{} {} {} 0 {}", drone_id, x, y, battery).unwrap();
+            }
+        }
+
+        writeln!(input, "{}", drone_scans.len()).unwrap();
+        for (drone_id, creature_id) in drone_scans.iter() {
+            writeln!(input, "{} {}", drone_id, creature_id).unwrap();
+        }
+
+        writeln!(input, "{}", visible.len()).unwrap();
+        for (id, x, y, vx, vy) in visible.iter() {
+            writeln!(input, "{} {} {} {} {}", id, x, y, vx, vy).unwrap();
+        }
+
+        writeln!(input, "{}", my_drone_ids.len() * positions.len()).unwrap();
+        for (drone_id, drone_x, drone_y, _) in drones.iter().filter(|(id, _, _, _)| id % 2 == 0) {
+            for (id, x, y, _, _) in positions.iter() {
+                let radar = RadarDir::between((*drone_x, *drone_y), (*x, *y));
+                writeln!(input, "{} {} {}", drone_id, id, radar.as_str()).unwrap();
+            }
+        }
+        input
+    };
+
+    state.update_state(&mut Cursor::new(turn_input([&[], &[]])));
+    state.update_state(&mut Cursor::new(turn_input([&my_saved, &foe_saved])));
+    state
+}
+
+// A move for every drone of both players, aiming anywhere including off the map
+fn random_moves(rng: &mut StdRng, state: &GameState) -> HashMap<i32, Move> {
+    state
+        .my_drones
+        .keys()
+        .chain(state.their_drones.keys())
+        .map(|drone_id| {
+            let should_move = rng.gen_bool(0.8);
+            let m = Move {
+                should_move,
+                x: should_move.then(|| rng.gen_range(-2000..=MAP_SIZE + 2000)),
+                y: should_move.then(|| rng.gen_range(-2000..=MAP_SIZE + 2000)),
+                light: rng.gen_bool(0.3),
+            };
+            (*drone_id, m)
+        })
+        .collect()
+}
+
+// Every turn of random moves from every random state, with the seed of the case
+fn for_each_turn(mut check: impl FnMut(u64, &GameState, &GameState)) {
+    for seed in 0..CASES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = random_state(&mut rng);
+
+        for _ in 0..TURNS {
+            let moves = random_moves(&mut rng, &state);
+            let before = state.clone();
+            state.apply_moves(&moves);
+            check(seed, &before, &state);
+        }
+    }
+}
+
+// Most points a player can earn by saving `newly_saved` on top of `saved`:
+// every creature and every completed combo counted once, doubled as if saved first
+fn max_points_for_save(state: &GameState, saved: &HashSet<i32>, newly_saved: &HashSet<i32>) -> i32 {
+    let all_saved = saved.union(newly_saved).copied().collect::<HashSet<_>>();
+    let mut points = newly_saved
+        .iter()
+        .map(|creature_id| 2 * state.creatures.get(creature_id).unwrap().get_score())
+        .sum::<i32>();
+
+    let fish = state
+        .creatures
+        .values()
+        .filter(|creature| creature.kind.is_fish())
+        .collect::<Vec<_>>();
+    let is_complete = |saved: &HashSet<i32>, group: &[i32]| {
+        group.iter().all(|creature_id| saved.contains(creature_id))
+    };
+    let mut groups = Vec::new();
+    for creature in fish.iter() {
+        let same_color = fish
+            .iter()
+            .filter(|other| other.color == creature.color)
+            .map(|other| other.id)
+            .collect::<Vec<_>>();
+        let same_kind = fish
+            .iter()
+            .filter(|other| other.kind == creature.kind)
+            .map(|other| other.id)
+            .collect::<Vec<_>>();
+        groups.push((same_color, COLOR_COMBO_POINTS));
+        groups.push((same_kind, TYPE_COMBO_POINTS));
+    }
+    groups.sort();
+    groups.dedup();
+
+    for (group, combo_points) in groups {
+        if is_complete(&all_saved, &group) && !is_complete(saved, &group) {
+            points += 2 * combo_points;
+        }
+    }
+    points
+}
+
+fn assert_drones_in_bounds(seed: u64, state: &GameState) {
+    for drone in state.my_drones.values().chain(state.their_drones.values()) {
+        assert!(
+            (0..=MAP_SIZE).contains(&drone.x) && (0..=MAP_SIZE).contains(&drone.y),
+            "seed {}: drone {} at ({}, {})",
+            seed,
+            drone.id,
+            drone.x,
+            drone.y
+        );
+        assert!(
+            (MIN_BATTERY..=MAX_BATTERY).contains(&drone.battery),
+            "seed {}: drone {} with battery {}",
+            seed,
+            drone.id,
+            drone.battery
+        );
+    }
+}
+
+#[test]
+fn drones_stay_on_the_map_with_battery_in_range() {
+    for_each_turn(|seed, _, after| assert_drones_in_bounds(seed, after));
+}
+
+#[test]
+fn scores_never_decrease() {
+    for_each_turn(|seed, before, after| {
+        assert!(after.my_score >= before.my_score, "seed {}", seed);
+        assert!(after.foe_score >= before.foe_score, "seed {}", seed);
+    });
+}
+
+#[test]
+fn saved_scans_stay_saved() {
+    for_each_turn(|seed, before, after| {
+        assert!(before.scans.is_subset(&after.scans), "seed {}", seed);
+        for is_mine in [true, false] {
+            assert!(
+                before
+                    .saved_creatures_for(is_mine)
+                    .is_subset(&after.saved_creatures_for(is_mine)),
+                "seed {}",
+                seed
+            );
+        }
+    });
+}
+
+#[test]
+fn creatures_are_scored_once() {
+    for_each_turn(|seed, before, after| {
+        let players = [
+            (true, before.my_score, after.my_score, after.my_scan_count),
+            (
+                false,
+                before.foe_score,
+                after.foe_score,
+                after.foe_scan_count,
+            ),
+        ];
+        for (is_mine, score_before, score_after, scan_count) in players {
+            let saved = before.saved_creatures_for(is_mine);
+            let now_saved = after.saved_creatures_for(is_mine);
+            let newly_saved = now_saved.difference(&saved).copied().collect();
+
+            assert!(
+                score_after - score_before <= max_points_for_save(before, &saved, &newly_saved),
+                "seed {}: {} points for saving {:?}",
+                seed,
+                score_after - score_before,
+                newly_saved
+            );
+            assert_eq!(scan_count as usize, now_saved.len(), "seed {}", seed);
+        }
+    });
+}
+
+// Whole matches of random moves through the referee, checking its state every turn
+// and that each player's parsed view agrees with it
+#[test]
+fn referee_keeps_the_invariants() {
+    for seed in 0..MATCHES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let league = LEAGUES[seed as usize % LEAGUES.len()];
+        let mut referee = Referee::new(seed, league);
+        let mut views = [0, 1].map(|_| {
+            let mut view = GameState::new(&mut Cursor::new(referee.creatures_input()));
+            view.league = league;
+            view
+        });
+
+        while !referee.state().is_terminal() {
+            let before = referee.state().clone();
+            let moves = random_moves(&mut rng, &before);
+            referee.step(&moves);
+            let after = referee.state();
+
+            assert_drones_in_bounds(seed, after);
+            assert!(after.my_score >= before.my_score, "seed {}", seed);
+            assert!(after.foe_score >= before.foe_score, "seed {}", seed);
+            assert!(before.scans.is_subset(&after.scans), "seed {}", seed);
+
+            for (player, view) in views.iter_mut().enumerate() {
+                view.update_state(&mut Cursor::new(referee.turn_input(player)));
+                let (my_score, foe_score) = if player == 0 {
+                    (after.my_score, after.foe_score)
+                } else {
+                    (after.foe_score, after.my_score)
+                };
+                assert_eq!((view.my_score, view.foe_score), (my_score, foe_score));
+
+                for drone in view.my_drones.values().chain(view.their_drones.values()) {
+                    let actual = after.drone(drone.id);
+                    assert_eq!(
+                        (drone.x, drone.y, drone.battery),
+                        (actual.x, actual.y, actual.battery),
+                        "seed {}: drone {} seen by player {}",
+                        seed,
+                        drone.id,
+                        player
+                    );
+                }
+            }
+        }
+    }
+}
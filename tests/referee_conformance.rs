@@ -0,0 +1,205 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{BufRead, Cursor},
+    path::{Path, PathBuf},
+};
+
+use fall_challenge_2023::{protocol::Move, state::GameState};
+
+// Rounding of positions may differ from the online referee by a unit
+const POSITION_TOLERANCE: i32 = 2;
+
+// A match log is a player's input stream, with after each turn's input the action of every
+// drone, the player's drones then the opponent's, each in id order. Logs of online matches go
+// in tests/logs/codingame: with `ECHO_INPUTS` on, the bot prints its input stream on stderr,
+// which the game's replay shows next to the actions of both players. Logs of local matches,
+// written by `replay --format log`, go in tests/logs/self_play and only check the forward model
+// against our own referee.
+struct Turn {
+    state: GameState,
+    moves: HashMap<i32, Move>,
+}
+
+fn parse_log(log: &str) -> Vec<Turn> {
+    let mut input = Cursor::new(log);
+    let mut state = GameState::new(&mut input);
+    let mut turns = Vec::new();
+
+    while !input.fill_buf().unwrap().is_empty() {
+        state.update_state(&mut input);
+
+        let mut moves = HashMap::new();
+        for drones in [&state.my_drones, &state.their_drones] {
+            let mut drone_ids = drones.keys().copied().collect::<Vec<_>>();
+            drone_ids.sort();
+            for drone_id in drone_ids {
+                let mut action = String::new();
+                input.read_line(&mut action).unwrap();
+                moves.insert(drone_id, Move::parse(&action).unwrap());
+            }
+        }
+        turns.push(Turn {
+            state: state.clone(),
+            moves,
+        });
+    }
+    turns
+}
+
+fn is_close(predicted: (i32, i32), actual: (i32, i32)) -> bool {
+    (predicted.0 - actual.0).abs() <= POSITION_TOLERANCE
+        && (predicted.1 - actual.1).abs() <= POSITION_TOLERANCE
+}
+
+// Differences between the forward model applied to a turn and the next turn's input.
+// Only what the player saw can be predicted, so creatures out of its sight are left out
+fn differences(turn: &Turn, next: &GameState) -> Vec<String> {
+    let mut differences = Vec::new();
+    let mut predicted = turn.state.clone();
+    predicted.apply_moves(&turn.moves);
+
+    // Creatures out of sight reach further than their extrapolated position, so the drone
+    // must be hit when the exact extrapolation hits it, and may only be hit when the wider
+    // reach of the forward model does
+    let mut exact = turn.state.clone();
+    for creature in exact.creatures.values_mut() {
+        if creature.x.is_some() {
+            creature.last_seen_turn = Some(turn.state.turn);
+        }
+    }
+    exact.apply_moves(&turn.moves);
+
+    let visible = turn
+        .state
+        .creatures
+        .values()
+        .filter(|creature| creature.last_seen_turn == Some(turn.state.turn))
+        .map(|creature| creature.id)
+        .collect::<HashSet<_>>();
+
+    for drone in next.my_drones.values().chain(next.their_drones.values()) {
+        let expected = predicted.drone(drone.id);
+        if !is_close((expected.x, expected.y), (drone.x, drone.y))
+            || expected.battery != drone.battery
+            || !(exact.drone(drone.id).emergency..=expected.emergency).contains(&drone.emergency)
+        {
+            differences.push(format!(
+                "drone {}: predicted ({}, {}) battery {} emergency {}, got ({}, {}) battery {} emergency {}",
+                drone.id,
+                expected.x,
+                expected.y,
+                expected.battery,
+                expected.emergency,
+                drone.x,
+                drone.y,
+                drone.battery,
+                drone.emergency
+            ));
+        }
+
+        let predicted_scans =
+            &predicted.unsaved_creatures_for_drone(drone.id, &HashSet::new()) & &visible;
+        let scans = &next.unsaved_creatures_for_drone(drone.id, &HashSet::new()) & &visible;
+        if predicted_scans != scans {
+            differences.push(format!(
+                "drone {} scans: predicted {:?}, got {:?}",
+                drone.id, predicted_scans, scans
+            ));
+        }
+    }
+
+    for is_mine in [true, false] {
+        let expected = predicted.saved_creatures_for(is_mine);
+        let saved = next.saved_creatures_for(is_mine);
+        if expected != saved {
+            differences.push(format!(
+                "saved by {}: predicted {:?}, got {:?}",
+                if is_mine { "player" } else { "opponent" },
+                expected,
+                saved
+            ));
+        }
+    }
+    if (predicted.my_score, predicted.foe_score) != (next.my_score, next.foe_score) {
+        differences.push(format!(
+            "scores: predicted {} - {}, got {} - {}",
+            predicted.my_score, predicted.foe_score, next.my_score, next.foe_score
+        ));
+    }
+
+    for creature in next.creatures.values() {
+        let is_seen = visible.contains(&creature.id) && creature.last_seen_turn == Some(next.turn);
+        if !is_seen {
+            continue;
+        }
+        let expected = predicted.creatures.get(&creature.id).unwrap();
+        let (Some(x), Some(y)) = (expected.x, expected.y) else {
+            differences.push(format!("creature {}: predicted gone", creature.id));
+            continue;
+        };
+        if !is_close((x, y), (creature.x.unwrap(), creature.y.unwrap())) {
+            differences.push(format!(
+                "creature {}: predicted ({}, {}), got ({}, {})",
+                creature.id,
+                x,
+                y,
+                creature.x.unwrap(),
+                creature.y.unwrap()
+            ));
+        }
+    }
+
+    differences
+}
+
+// Match logs of tests/logs/<kind>, none when the directory does not exist
+fn log_paths(kind: &str) -> Vec<PathBuf> {
+    let logs_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/logs")
+        .join(kind);
+    let Ok(entries) = fs::read_dir(&logs_dir) else {
+        return Vec::new();
+    };
+    let mut paths = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "log"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+fn check_logs(paths: &[PathBuf]) {
+    let mut failures = Vec::new();
+    for path in paths {
+        let turns = parse_log(&fs::read_to_string(path).unwrap());
+        for pair in turns.windows(2) {
+            for difference in differences(&pair[0], &pair[1].state) {
+                failures.push(format!(
+                    "{} turn {}: {}",
+                    path.file_name().unwrap().to_string_lossy(),
+                    pair[0].state.turn,
+                    difference
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// Ignored until match logs recorded on CodinGame are committed in tests/logs/codingame
+#[test]
+#[ignore = "no match log recorded on CodinGame yet"]
+fn forward_model_matches_online_matches() {
+    let paths = log_paths("codingame");
+    assert!(!paths.is_empty(), "no match log in tests/logs/codingame");
+    check_logs(&paths);
+}
+
+#[test]
+fn forward_model_matches_self_play_matches() {
+    let paths = log_paths("self_play");
+    assert!(!paths.is_empty(), "no match log in tests/logs/self_play");
+    check_logs(&paths);
+}
@@ -0,0 +1,55 @@
+use std::{fmt::Write, io::Cursor};
+
+use fall_challenge_2023::{protocol::RadarDir, state::GameState};
+
+const FISH_IDS: std::ops::RangeInclusive<i32> = 4..=15;
+
+// Both my drones near a fish worth 3 points that I saved and the foe has not,
+// swimming toward the left side of the map
+fn fish_near_the_side() -> GameState {
+    let mut input = format!("{}\n", FISH_IDS.count());
+    for id in FISH_IDS {
+        writeln!(input, "{} {} {}", id, (id - 4) / 3, (id - 4) % 3).unwrap();
+    }
+    let mut state = GameState::new(&mut Cursor::new(input));
+
+    let drones = [(0, 1500), (2, 3500)];
+    let turn_input = |saved: &[i32]| {
+        let mut input = format!("0\n0\n{}\n", saved.len());
+        for creature_id in saved {
+            writeln!(input, "{}", creature_id).unwrap();
+        }
+        input += "0\n2\n";
+        for (drone_id, x) in drones {
+            writeln!(input, "{} {} 8000 0 30", drone_id, x).unwrap();
+        }
+        input += "2\n1 6000 500 0 30\n3 8000 500 0 30\n";
+        input += "0\n";
+        input += "1\n6 700 8000 -200 0\n";
+        writeln!(input, "{}", drones.len() * FISH_IDS.count()).unwrap();
+        for (drone_id, x) in drones {
+            for id in FISH_IDS {
+                let radar = if id == 6 {
+                    RadarDir::between((x, 8000), (700, 8000))
+                } else {
+                    RadarDir::between((x, 8000), (id * 600, 5000))
+                };
+                writeln!(input, "{} {} {}", drone_id, id, radar.as_str()).unwrap();
+            }
+        }
+        input
+    };
+    state.update_state(&mut Cursor::new(turn_input(&[])));
+    state.update_state(&mut Cursor::new(turn_input(&[6])));
+    state
+}
+
+#[test]
+fn a_single_drone_scares_each_fish() {
+    let state = fish_near_the_side();
+    assert_eq!(state.scare_candidates(), vec![6]);
+
+    // The closest drone chases the fish, the other one keeps to its route
+    assert!(state.plan_scare(0).is_some());
+    assert!(state.plan_scare(2).is_none());
+}